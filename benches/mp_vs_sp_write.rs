@@ -6,7 +6,7 @@ use chute::{mpmc, spmc};
 const THREADS: usize = 4;
 
 const N: usize = 4;
-struct Value([usize; N]);
+struct Value(#[allow(dead_code)] [usize; N]);
 impl From<usize> for Value{
     #[inline]
     fn from(value: usize) -> Self {
@@ -15,7 +15,7 @@ impl From<usize> for Value{
 }
 
 fn mpmc_write(n: usize) {
-    let mut queue = mpmc::Queue::new();
+    let queue = mpmc::Queue::new();
     
     let mut joins = Vec::new();
     for _ in 0..THREADS {
@@ -33,11 +33,11 @@ fn mpmc_write(n: usize) {
 }
 
 fn spmc_write(n: usize) {
-    let mut queue: Arc<spin::Mutex<spmc::Queue<_>>> = Default::default();
+    let queue: Arc<spin::Mutex<spmc::Queue<_>>> = Default::default();
     
     let mut joins = Vec::new();
     for _ in 0..THREADS {
-        let mut queue = queue.clone();
+        let queue = queue.clone();
         joins.push(std::thread::spawn(move || {
             for i in 0..n {
                 queue.lock().push(Value::from(i));
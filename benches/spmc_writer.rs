@@ -0,0 +1,35 @@
+use std::hint::black_box;
+use criterion::{criterion_group, criterion_main, Criterion};
+use chute::spmc;
+
+fn queue_push(n: usize) {
+    let mut queue = spmc::Queue::new();
+    for i in 0..n {
+        queue.push(i);
+    }
+}
+
+fn queue_push_notify(n: usize) {
+    let mut queue = spmc::Queue::new();
+    for i in 0..n {
+        queue.push_notify(i);
+    }
+}
+
+fn writer_push(n: usize) {
+    let mut queue = spmc::Queue::new();
+    let mut writer = queue.writer();
+    for i in 0..n {
+        writer.push(i);
+    }
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+    const COUNT: usize = 100000; 
+    c.bench_function("spmc Queue::push", |b| b.iter(|| queue_push(black_box(COUNT))));
+    c.bench_function("spmc Queue::push_notify", |b| b.iter(|| queue_push_notify(black_box(COUNT))));
+    c.bench_function("spmc Writer::push", |b| b.iter(|| writer_push(black_box(COUNT))));
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
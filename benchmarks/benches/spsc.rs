@@ -4,6 +4,31 @@ use criterion::{criterion_group, criterion_main, Criterion};
 mod common;
 use common::*;
 
+pub fn chute_spsc(){
+    let (mut producer, mut consumer) = chute::spsc::queue();
+    
+    let wt = std::thread::spawn(move || {
+        for i in 0..COUNT {
+            producer.push(message::new(i));
+        }
+    });
+
+    let rt = std::thread::spawn(move || {
+        for _ in 0..COUNT {
+            loop{
+                if let None = consumer.next(){
+                    yield_fn();
+                } else {
+                    break;
+                }
+            }
+        }
+    });
+    
+    wt.join().unwrap();
+    rt.join().unwrap();
+}
+
 pub fn chute_spmc(){
     let mut queue = chute::spmc::Queue::new();
     let mut reader = queue.reader();
@@ -97,6 +122,7 @@ pub fn flume_unbounded(){
 
 fn criterion_benchmark(c: &mut Criterion) {
     let mut group = c.benchmark_group("spsc");
+    group.bench_function("chute::spsc", |b| b.iter(|| chute_spsc()));
     group.bench_function("chute::spmc", |b| b.iter(|| chute_spmc()));
     group.bench_function("chute::mpmc", |b| b.iter(|| chute_mpmc()));
     group.bench_function("crossbeam::unbounded", |b| b.iter(|| crossbeam_unbounded()));
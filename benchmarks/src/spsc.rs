@@ -18,6 +18,7 @@ pub fn spsc(dir_name: impl AsRef<Path>) {
     };
     
     let all: Vec<(String, f64)> = vec![
+        (str!("chute::spsc"), read("chute__spsc")),
         (str!("chute::spmc"), read("chute__spmc")),
         (str!("chute::mpmc"), read("chute__mpmc")),
         (str!("crossbeam::\nunbounded"), read("crossbeam__unbounded")),
@@ -0,0 +1,84 @@
+use alloc::vec::Vec;
+use crate::LendingReader;
+
+/// Reader that batches messages by count, flushing on idle.
+///
+/// Accumulates up to `max_batch` cloned messages. If reader runs out of
+/// messages before that - yields what it has right away, without waiting
+/// for more.
+///
+/// ```
+/// # use chute::BatchReader;
+/// let mut queue = chute::spmc::Queue::new();
+/// let mut reader = BatchReader::new(queue.reader(), 2);
+/// for i in 0..3 {
+///     queue.push(i);
+/// }
+/// assert_eq!(reader.next_batch(), Some(vec![0, 1]));
+/// assert_eq!(reader.next_batch(), Some(vec![2]));
+/// assert_eq!(reader.next_batch(), None);
+/// ```
+pub struct BatchReader<R> {
+    reader: R,
+    max_batch: usize,
+}
+
+impl<R> BatchReader<R>
+where
+    R: LendingReader<Item: Clone>
+{
+    /// # Panics
+    ///
+    /// Panics if `max_batch` is 0.
+    #[inline]
+    pub fn new(reader: R, max_batch: usize) -> Self {
+        assert!(max_batch != 0, "Batch size should be positive.");
+        Self{ reader, max_batch }
+    }
+
+    /// Returns up to `max_batch` messages, or `None` if there are no messages.
+    pub fn next_batch(&mut self) -> Option<Vec<R::Item>> {
+        let first = self.reader.next()?.clone();
+        let mut batch = Vec::with_capacity(self.max_batch);
+        batch.push(first);
+        while batch.len() < self.max_batch {
+            match self.reader.next() {
+                Some(value) => batch.push(value.clone()),
+                None => break,
+            }
+        }
+        Some(batch)
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use crate::block::BLOCK_SIZE;
+    use crate::{mpmc, BatchReader};
+
+    #[test]
+    fn batch_test() {
+        let queue: std::sync::Arc<mpmc::Queue<usize>> = Default::default();
+        let mut reader = BatchReader::new(queue.reader(), 100);
+        assert_eq!(reader.next_batch(), None);
+
+        for i in 0..BLOCK_SIZE+50 {
+            queue.blocking_push(i);
+        }
+        let mut all = Vec::new();
+        while let Some(batch) = reader.next_batch() {
+            assert!(batch.len() <= 100);
+            all.extend(batch);
+        }
+        assert_eq!(all, (0..BLOCK_SIZE+50).collect::<Vec<_>>());
+
+        // Idle flush.
+        queue.blocking_push(1);
+        assert_eq!(reader.next_batch(), Some(vec![1]));
+    }
+}
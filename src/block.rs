@@ -1,16 +1,63 @@
-use std::alloc::{alloc, dealloc, handle_alloc_error, Layout};
-use std::{cmp, mem, ptr};
-use std::cell::UnsafeCell;
-use std::marker::PhantomData;
-use std::mem::{ManuallyDrop, MaybeUninit};
-use std::ops::Deref;
-use std::ptr::{null_mut, NonNull};
-use std::sync::atomic;
-use std::sync::atomic::{AtomicPtr, AtomicU64, AtomicUsize, Ordering};
+use alloc::alloc::{alloc, dealloc, handle_alloc_error, GlobalAlloc, Layout};
+use core::{cmp, mem, ptr, slice};
+use core::marker::PhantomData;
+use core::mem::ManuallyDrop;
+use core::ops::Deref;
+use core::ptr::{null_mut, NonNull};
+use alloc::sync::Arc;
+use crate::sync::{fence, AtomicBool, AtomicPtr, AtomicU64, AtomicUsize, Ordering};
 use branch_hints::unlikely;
 
 pub(crate) const BLOCK_SIZE: usize = if cfg!(miri) { 128 } else { 4096 };
 
+/// Allocator of block memory. `None` - global allocator.
+pub(crate) type BlockAllocator = Arc<dyn GlobalAlloc + Send + Sync>;
+
+/// [Allocator] as [BlockAllocator].
+/// 
+/// [Allocator]: core::alloc::Allocator
+#[cfg(feature = "allocator_api")]
+pub(crate) struct AllocatorAdapter<A>(pub A);
+
+#[cfg(feature = "allocator_api")]
+unsafe impl<A: core::alloc::Allocator> GlobalAlloc for AllocatorAdapter<A> {
+    #[inline]
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        match self.0.allocate(layout) {
+            Ok(ptr) => ptr.cast().as_ptr(),
+            Err(_) => null_mut(),
+        }
+    }
+    
+    #[inline]
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.0.deallocate(NonNull::new_unchecked(ptr), layout)
+    }
+}
+
+/// Block allocation size limit in bytes. 64MiB by default.
+/// 
+/// Can be overridden with `CHUTE_MAX_BLOCK_BYTES` environment variable
+/// at compile time.
+pub(crate) const MAX_BLOCK_BYTES: usize = match option_env!("CHUTE_MAX_BLOCK_BYTES") {
+    Some(str) => parse_usize(str),
+    None => 64 << 20,
+};
+
+const fn parse_usize(str: &str) -> usize {
+    let bytes = str.as_bytes();
+    assert!(!bytes.is_empty(), "CHUTE_MAX_BLOCK_BYTES is empty.");
+    let mut value: usize = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        let digit = bytes[i];
+        assert!(digit.is_ascii_digit(), "CHUTE_MAX_BLOCK_BYTES should be a decimal number.");
+        value = value * 10 + (digit - b'0') as usize;
+        i += 1;
+    }
+    value
+}
+
 #[repr(align(64))]
 pub(crate) struct CacheLineAlign<T>(T);
 impl<T: Default> Default for CacheLineAlign<T> {
@@ -34,7 +81,18 @@ impl<T> Deref for CacheLineAlign<T>{
     }
 }
 
-//#[repr(C)]
+/// Block header.
+/// 
+/// Followed in the same allocation by:
+/// * `mem       : [MaybeUninit<T>; capacity]`
+/// * `bit_blocks: [AtomicU64; capacity/64]` - not used in spmc.
+/// 
+/// Tombstone bits live in a separate allocation, see [tombstones].
+/// 
+/// [tombstones]: Self::tombstones
+/// 
+/// Since `&Block` covers header only - `mem` and `bit_blocks` are accessed through 
+/// the allocation pointer. See [BlockArc::mem()], [BlockArc::bit_blocks()].
 pub(crate) struct Block<T> {
     /// # spmc 
     /// 
@@ -48,40 +106,164 @@ pub(crate) struct Block<T> {
     /// It's len for writers. Readers use `bit_blocks` for getting
     /// actual block len.
     /// 
-    /// Will be >= BLOCK_SIZE after block is fully written.
+    /// Will be >= capacity after block is fully written. Failed pushes keep
+    /// incrementing it, but each push fails on a block at most once - after
+    /// that, writer moves to the next block. So it stays below 
+    /// `capacity + concurrent pushes`, and never wraps.
     // Aligning with cache-line size gives us +10% perf.
     pub len : CacheLineAlign<AtomicUsize>,
     use_count : AtomicUsize,           // When decreases to 0 - frees itself
     pub next  : AtomicPtr<Self>,
     
-    // This is not used in spmc.
-    pub bit_blocks: [AtomicU64; BLOCK_SIZE/64],
-    /*pub*/ mem : UnsafeCell<[MaybeUninit<T>; BLOCK_SIZE]>,
+    /// Block sequence number in queue. First block is 0.
+    pub seq   : usize,
+    
+    /// Position of the first block message in queue.
+    /// 
+    /// All blocks except the last one are full, so 
+    /// `start + index` is a message position in queue.
+    pub start : usize,
+    
+    /// Number of message slots. Multiple of 64, not greater than queue 
+    /// block size.
+    pub capacity: usize,
+    
+    /// User metadata. Set before block is linked to queue, 
+    /// never changes afterward.
+    pub meta  : AtomicU64,
+    
+    /// Overwrite `mem` with zeros on drop. Inherited by the next block. 
+    pub zeroize: AtomicBool,
+    
+    /// Touch `mem` pages on allocation. Inherited by the next block.
+    prefault: AtomicBool,
+    
+    /// mpmc only. `[AtomicU64; capacity/64]` of aborted slots, that readers 
+    /// skip. Allocated with block allocator, on the first abort in the block - 
+    /// null most of the time.
+    /// 
+    /// Tombstone bit is set before slot's bitblock bit. So anyone who sees
+    /// slot as published, sees its tombstone as well.
+    tombstones: AtomicPtr<AtomicU64>,
+    
+    /// Allocated with, and freed through. Inherited by the next block.
+    pub allocator: Option<BlockAllocator>,
+    
+    phantom_data: PhantomData<T>
 }
 
 impl<T> Block<T>{
+    /// Checks queue block size. Evaluate at compile time, in `const {}`.
+    pub const fn check_capacity(capacity: usize) {
+        assert!(
+            capacity != 0 && capacity.is_multiple_of(64), 
+            "Block size should be a positive multiple of 64."
+        );
+        assert!(
+            Self::bit_blocks_offset(capacity) + capacity/8 <= MAX_BLOCK_BYTES,
+            "Message type is too large: block would exceed CHUTE_MAX_BLOCK_BYTES."
+        );
+    }
+    
+    const MEM_OFFSET: usize = mem::size_of::<Self>().next_multiple_of(mem::align_of::<T>());
+    
+    #[inline]
+    const fn bit_blocks_offset(capacity: usize) -> usize {
+        (Self::MEM_OFFSET + capacity * mem::size_of::<T>())
+            .next_multiple_of(mem::align_of::<AtomicU64>())
+    }
+    
+    #[inline]
+    fn layout(capacity: usize) -> Layout {
+        let align = cmp::max(mem::align_of::<Self>(), mem::align_of::<T>());
+        let size  = Self::bit_blocks_offset(capacity) + capacity/64 * mem::size_of::<AtomicU64>();
+        Layout::from_size_align(size, align).unwrap()
+    }
+    
     #[must_use]
-    pub fn with_counter(counter: usize) -> BlockArc<T> {
+    fn allocate(counter: usize, seq: usize, start: usize, capacity: usize, allocator: Option<BlockAllocator>) -> BlockArc<T> {
+        debug_assert!(capacity != 0 && capacity.is_multiple_of(64));
         unsafe{
-            let layout = Layout::new::<Self>();
-            let ptr = alloc(layout) as *mut Self;
+            let layout = Self::layout(capacity);
+            let ptr = match &allocator {
+                Some(allocator) => allocator.alloc(layout),
+                None => alloc(layout),
+            } as *mut Self;
             if ptr.is_null() {
                 handle_alloc_error(layout);
             }
 
-            (*ptr).len = Default::default();
-            (*ptr).use_count = AtomicUsize::new(counter);
-            (*ptr).next = AtomicPtr::new(null_mut());
+            ptr.write(Self{
+                len: Default::default(),
+                use_count: AtomicUsize::new(counter),
+                next: AtomicPtr::new(null_mut()),
+                seq,
+                start,
+                capacity,
+                meta: AtomicU64::new(0),
+                zeroize: AtomicBool::new(false),
+                prefault: AtomicBool::new(false),
+                tombstones: AtomicPtr::new(null_mut()),
+                allocator,
+                phantom_data: PhantomData,
+            });
             
-            (*ptr).bit_blocks = core::array::from_fn(|_|AtomicU64::new(0)); 
+            let bit_blocks: *mut AtomicU64 = ptr.cast::<u8>().add(Self::bit_blocks_offset(capacity)).cast();
+            for i in 0..capacity/64 {
+                bit_blocks.add(i).write(AtomicU64::new(0));
+            }
         
             BlockArc::from_raw(NonNull::new_unchecked(ptr))
         }
     }
     
+    /// First block in queue.
+    #[allow(clippy::new_ret_no_self)]
     #[must_use]
-    pub fn new() -> BlockArc<T> {
-        Self::with_counter(1)
+    pub fn new(capacity: usize) -> BlockArc<T> {
+        Self::allocate(1, 0, 0, capacity, None)
+    }
+    
+    /// First block in queue, to replace this untouched one. 
+    /// Options are inherited.
+    #[must_use]
+    pub fn new_first(&self, capacity: usize, allocator: Option<BlockAllocator>) -> BlockArc<T> {
+        let block = Self::allocate(1, 0, 0, capacity, allocator);
+        block.inherit_options(self);
+        block
+    }
+    
+    /// Block to follow this one. Not linked.
+    #[must_use]
+    pub fn new_next(&self, counter: usize, capacity: usize) -> BlockArc<T> {
+        let block = Self::allocate(counter, self.seq + 1, self.start + self.capacity, capacity, self.allocator.clone());
+        block.inherit_options(self);
+        block
+    }
+    
+    /// Publish `len` messages to readers. spmc/spsc writer side.
+    /// 
+    /// Together with [load_len()], this is the whole spmc/spsc 
+    /// synchronization - swap these to experiment with other schemes.
+    /// 
+    /// [load_len()]: Self::load_len
+    #[inline]
+    pub fn publish_len(&self, len: usize) {
+        self.len.store(len, Ordering::Release);
+    }
+    
+    /// Number of published messages. spmc/spsc reader side.
+    /// 
+    /// Messages below returned len are visible to the current thread.
+    #[inline]
+    pub fn load_len(&self) -> usize {
+        self.len.load(Ordering::Acquire)
+    }
+    
+    /// Is this block referenced only by its owner?
+    #[inline]
+    pub fn is_unique(&self) -> bool {
+        self.use_count.load(Ordering::Acquire) == 1
     }
     
     #[inline]
@@ -91,15 +273,35 @@ impl<T> Block<T>{
     
     #[inline(never)]
     #[cold]
-    unsafe fn drop_this(mut this: NonNull<Self>){
+    unsafe fn drop_this(this: NonNull<Self>){
         debug_assert!(this.as_ref().use_count.load(Ordering::Acquire) == 0);
+        let capacity = this.as_ref().capacity;
+        let mem = Self::mem_ptr(this);
         
         // drop mem
         if mem::needs_drop::<T>() {
-            let len = cmp::min(this.as_ref().len.load(Ordering::Acquire), BLOCK_SIZE);
-            let mem = this.as_mut().mem.get_mut();
+            let len = cmp::min(this.as_ref().len.load(Ordering::Acquire), capacity);
+            let has_tombstones = this.as_ref().has_tombstones();
             for i in 0..len {
-                ptr::drop_in_place(mem.get_unchecked_mut(i).assume_init_mut());
+                if has_tombstones && this.as_ref().is_tombstone(i) {
+                    continue;
+                }
+                ptr::drop_in_place(mem.add(i));
+            }
+        }
+        
+        // drop tombstones
+        let tombstones = this.as_ref().tombstones.load(Ordering::Acquire);
+        if !tombstones.is_null() {
+            this.as_ref().dealloc_tombstones(tombstones);
+        }
+        
+        // zeroize mem, after all destructors 
+        if this.as_ref().zeroize.load(Ordering::Relaxed) {
+            let mem: *mut u8 = mem.cast();
+            for i in 0..capacity * mem::size_of::<T>() {
+                // Volatile, so it will not be optimized away as dead store.
+                ptr::write_volatile(mem.add(i), 0);
             }
         }
         
@@ -110,8 +312,12 @@ impl<T> Block<T>{
         }
         
         // dealloc
-        let layout = Layout::new::<Self>();
-        dealloc(this.as_ptr().cast(), layout);
+        let allocator = ptr::read(&this.as_ref().allocator);
+        let layout = Self::layout(capacity);
+        match allocator {
+            Some(allocator) => allocator.dealloc(this.as_ptr().cast(), layout),
+            None => dealloc(this.as_ptr().cast(), layout),
+        }
     }
     
     #[inline]
@@ -120,14 +326,23 @@ impl<T> Block<T>{
         let prev = this.as_ref().use_count.fetch_sub(1, Ordering::Release);
         if prev == 1 {
              // See Arc::drop implementation, for this fence rationale.
-            atomic::fence(Ordering::Acquire);
+            fence(Ordering::Acquire);
             Self::drop_this(this);
         }
     }
     
+    /// `this` must be the allocation pointer, not derived from `&Block`.
+    #[inline]
+    pub unsafe fn mem_ptr(this: NonNull<Self>) -> *mut T {
+        this.as_ptr().cast::<u8>().add(Self::MEM_OFFSET).cast()
+    }
+    
+    /// `this` must be the allocation pointer, not derived from `&Block`.
     #[inline]
-    pub fn mem(&self) -> *const T {
-        self.mem.get().cast()
+    pub unsafe fn bit_blocks<'a>(this: NonNull<Self>) -> &'a [AtomicU64] {
+        let capacity = this.as_ref().capacity;
+        let ptr = this.as_ptr().cast::<u8>().add(Self::bit_blocks_offset(capacity));
+        slice::from_raw_parts(ptr.cast(), capacity/64)
     }
     
     // TODO: remove ordering param.
@@ -146,20 +361,57 @@ impl<T> Block<T>{
         }
     }
     
+    /// Is `other` this block, or one of the blocks after it?
+    /// 
+    /// Walks the chain, so this is O(distance).
+    pub fn is_followed_by(&self, other: &Self) -> bool {
+        let mut block = self;
+        while block.seq < other.seq {
+            let next = block.next.load(Ordering::Acquire);
+            match unsafe{ next.as_ref() } {
+                Some(next) => block = next,
+                None => return false,
+            }
+        }
+        ptr::eq(block, other)
+    }
+    
     #[inline]
-    pub fn try_push(&self, value: T) -> Result<(), T> {
-        let occupied_len = self.len.fetch_add(1, Ordering::AcqRel);
+    pub fn info(&self) -> BlockInfo {
+        BlockInfo{
+            seq: self.seq,
+            len: cmp::min(self.len.load(Ordering::Acquire), self.capacity),
+            capacity: self.capacity,
+            use_count: self.use_count.load(Ordering::Relaxed),
+        }
+    }
+    
+    /// Calls `f` for this block, and all blocks after it.
+    pub fn for_each(&self, mut f: impl FnMut(&BlockInfo)) {
+        let mut block = self;
+        loop {
+            f(&block.info());
+            let next = block.next.load(Ordering::Acquire);
+            match unsafe{ next.as_ref() } {
+                Some(next) => block = next,
+                None => return,
+            }
+        }
+    }
+    
+    /// `this` must be the allocation pointer, not derived from `&Block`.
+    #[inline]
+    pub unsafe fn try_push(this: NonNull<Self>, value: T) -> Result<(), T> {
+        let block = this.as_ref();
+        let occupied_len = block.len.fetch_add(1, Ordering::AcqRel);
         
-        if unlikely(occupied_len >= BLOCK_SIZE) {
+        if unlikely(occupied_len >= block.capacity) {
             return Err(value);
         }
 
         // Actually save value.
         let index = occupied_len;
-        unsafe{
-            let mem = self.mem().cast_mut();
-            mem.add(index).write(value);
-        }
+        Self::mem_ptr(this).add(index).write(value);
 
         // Update bitblock, indicating that value is ready to read.
         {
@@ -167,12 +419,150 @@ impl<T> Block<T>{
             let bit_index = index % 64;
             
             let bitmask = 1 << bit_index;
-            let atomic_block = unsafe{ self.bit_blocks.get_unchecked(bit_block_index) };
+            let atomic_block = Self::bit_blocks(this).get_unchecked(bit_block_index);
             atomic_block.fetch_or(bitmask, Ordering::Release);
         }
         
         Ok(())
-    }    
+    }
+    
+    /// Reserves `n` contiguous slots, if they fit in block. 
+    /// Returns the first reserved index.
+    /// 
+    /// Unlike [try_push()], does not touch `len` on failure.
+    /// 
+    /// [try_push()]: Self::try_push
+    #[inline]
+    pub fn try_reserve(&self, n: usize) -> Option<usize> {
+        let mut len = self.len.load(Ordering::Relaxed);
+        loop {
+            if len + n > self.capacity {
+                return None;
+            }
+            match self.len.compare_exchange_weak(len, len + n, Ordering::AcqRel, Ordering::Relaxed) {
+                Ok(_) => return Some(len),
+                Err(actual) => len = actual,
+            }
+        }
+    }
+    
+    /// Are there aborted slots in block?
+    /// 
+    /// Readers should re-check after each `bit_blocks` load, that extends 
+    /// their len.
+    #[inline]
+    pub fn has_tombstones(&self) -> bool {
+        !self.tombstones.load(Ordering::Acquire).is_null()
+    }
+    
+    /// Is slot at `index` aborted? Slot must be published.
+    #[inline]
+    pub fn is_tombstone(&self, index: usize) -> bool {
+        let tombstones = self.tombstones.load(Ordering::Acquire);
+        if tombstones.is_null() {
+            return false;
+        }
+        let bit_block = unsafe{ &*tombstones.add(index / 64) }.load(Ordering::Relaxed);
+        bit_block & (1 << (index % 64)) != 0
+    }
+    
+    #[inline]
+    fn tombstones_layout(&self) -> Layout {
+        Layout::array::<AtomicU64>(self.capacity/64).unwrap()
+    }
+    
+    /// Zeroed `[AtomicU64; capacity/64]`, from the block allocator.
+    unsafe fn alloc_tombstones(&self) -> *mut AtomicU64 {
+        let layout = self.tombstones_layout();
+        let ptr = match &self.allocator {
+            Some(allocator) => allocator.alloc(layout),
+            None => alloc(layout),
+        } as *mut AtomicU64;
+        if ptr.is_null() {
+            handle_alloc_error(layout);
+        }
+        for i in 0..self.capacity/64 {
+            ptr.add(i).write(AtomicU64::new(0));
+        }
+        ptr
+    }
+    
+    unsafe fn dealloc_tombstones(&self, ptr: *mut AtomicU64) {
+        let layout = self.tombstones_layout();
+        match &self.allocator {
+            Some(allocator) => allocator.dealloc(ptr.cast(), layout),
+            None => dealloc(ptr.cast(), layout),
+        }
+    }
+    
+    /// Publishes reserved slot at `index` as tombstone, without writing
+    /// a value. Readers skip it.
+    /// 
+    /// `this` must be the allocation pointer, not derived from `&Block`.
+    #[cold]
+    #[inline(never)]
+    pub unsafe fn abort(this: NonNull<Self>, index: usize) {
+        let block = this.as_ref();
+        let mut tombstones = block.tombstones.load(Ordering::Acquire);
+        if tombstones.is_null() {
+            let new = block.alloc_tombstones();
+            match block.tombstones.compare_exchange(null_mut(), new, Ordering::AcqRel, Ordering::Acquire) {
+                Ok(_) => tombstones = new,
+                Err(actual) => {
+                    // Other writer was first.
+                    block.dealloc_tombstones(new);
+                    tombstones = actual;
+                }
+            }
+        }
+        (*tombstones.add(index / 64)).fetch_or(1 << (index % 64), Ordering::Relaxed);
+        
+        // Release tombstone with slot bit.
+        Self::publish(this, index, 1);
+    }
+    
+    /// Marks `index..index+n` slots as ready to read.
+    /// 
+    /// Bits are set from high to low. `trailing_ones()` stops at the first
+    /// unset bit, so readers see either none or all of the range.
+    /// 
+    /// `this` must be the allocation pointer, not derived from `&Block`.
+    pub unsafe fn publish(this: NonNull<Self>, index: usize, n: usize) {
+        if n == 0 {
+            return;
+        }
+        let end = index + n;
+        let mut bit_block_index = (end - 1) / 64;
+        loop {
+            let from = cmp::max(index, bit_block_index * 64);
+            let to   = cmp::min(end, bit_block_index * 64 + 64);
+            let count = to - from;
+            let bitmask = if count == 64 { u64::MAX } else { ((1 << count) - 1) << (from % 64) };
+            let atomic_block = Self::bit_blocks(this).get_unchecked(bit_block_index);
+            atomic_block.fetch_or(bitmask, Ordering::Release);
+            
+            if from == index {
+                return;
+            }
+            bit_block_index -= 1;
+        }
+    }
+}
+
+/// Best-effort snapshot of the queue block state.
+/// 
+/// See `Reader::for_each_block()`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BlockInfo {
+    /// Block sequence number in queue. First block is 0.
+    pub seq: usize,
+    /// Number of occupied slots. For mpmc this includes 
+    /// messages being written.
+    pub len: usize,
+    pub capacity: usize,
+    /// Number of readers, writers, queue and previous block, 
+    /// that keep this block alive.
+    pub use_count: usize,
 }
 
 pub(crate) struct BlockArc<T> {
@@ -196,7 +586,151 @@ impl<T> BlockArc<T>{
     pub fn as_non_null(&mut self) -> NonNull<Block<T>> {
         self.ptr
     }
+    
+    #[inline]
+    pub fn mem(&self) -> *mut T {
+        unsafe{ Block::mem_ptr(self.ptr) }
+    }
+    
+    #[inline]
+    pub fn bit_blocks(&self) -> &[AtomicU64] {
+        unsafe{ Block::bit_blocks(self.ptr) }
+    }
+    
+    #[inline]
+    pub fn try_push(&self, value: T) -> Result<(), T> {
+        unsafe{ Block::try_push(self.ptr, value) }
+    }
+    
+    /// Copy zeroize and prefault settings of `from`. For just allocated block.
+    #[inline]
+    pub fn inherit_options(&self, from: &Block<T>) {
+        self.zeroize.store(from.zeroize.load(Ordering::Relaxed), Ordering::Relaxed);
+        let prefault = from.prefault.load(Ordering::Relaxed);
+        self.prefault.store(prefault, Ordering::Relaxed);
+        if prefault {
+            self.prefault_mem();
+        }
+    }
+    
+    /// Set prefault for blocks, that follow this one. 
+    /// 
+    /// This block is prefaulted only if nothing was written to it, and
+    /// nobody else holds it - otherwise it is left as is.
+    pub fn set_prefault(&self, prefault: bool) {
+        self.prefault.store(prefault, Ordering::Relaxed);
+        if prefault && self.len.load(Ordering::Acquire) == 0 && self.is_unique() {
+            self.prefault_mem();
+        }
+    }
+    
+    /// Touch each page of block `mem` now, so the first writes to it do 
+    /// not page-fault. Block must have no messages.
+    fn prefault_mem(&self) {
+        const PAGE_SIZE: usize = 4096;
+        let mem: *mut u8 = self.mem().cast();
+        let size = self.capacity * mem::size_of::<T>();
+        for offset in (0..size).step_by(PAGE_SIZE) {
+            // Volatile, so it will not be optimized away.
+            // Slots are uninitialized - nothing to overwrite.
+            unsafe{ ptr::write_volatile(mem.add(offset), 0); }
+        }
+    }
+    
+    /// Moves `n` values from `src` to reserved slots, starting from `index`.
+    #[inline]
+    pub unsafe fn write(&self, index: usize, src: *const T, n: usize) {
+        ptr::copy_nonoverlapping(src, self.mem().add(index), n);
+    }
+    
+    #[inline]
+    pub unsafe fn publish(&self, index: usize, n: usize) {
+        Block::publish(self.ptr, index, n)
+    }
+    
+    #[inline]
+    pub unsafe fn abort(&self, index: usize) {
+        Block::abort(self.ptr, index)
+    }
+}
+/// Moves messages out of blocks, while blocks are uniquely owned.
+/// 
+/// Stops at the first shared block - since all blocks after it are 
+/// reachable by someone else as well.
+pub(crate) struct BlockDrain<T> {
+    block: Option<BlockArc<T>>,
+    index: usize,
+    len  : usize,
+}
+
+impl<T> BlockDrain<T> {
+    /// Drain from `index` of `block`.
+    #[inline]
+    pub fn new(block: BlockArc<T>, index: usize) -> Self {
+        let mut this = Self{ block: None, index: 0, len: 0 };
+        this.enter(block, index);
+        this
+    }
+    
+    fn enter(&mut self, block: BlockArc<T>, index: usize) {
+        // Acquire in is_unique() syncs with writers' and readers' drops.
+        if !block.is_unique() {
+            self.block = None;
+            return;
+        }
+        self.index = index;
+        self.len   = cmp::min(block.len.load(Ordering::Relaxed), block.capacity);
+        // From now on, block drops only messages before `index`.
+        // We take care of the rest.
+        block.len.store(index, Ordering::Relaxed);
+        self.block = Some(block);
+    }
+}
+
+impl<T> Iterator for BlockDrain<T> {
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        loop {
+            let block = self.block.as_ref()?;
+            if self.index < self.len {
+                let index = self.index;
+                self.index += 1;
+                if block.is_tombstone(index) {
+                    continue;
+                }
+                return Some(unsafe{ block.mem().add(index).read() });
+            }
+            
+            // Release current block first - so next one may become unique.
+            let next_block = block.try_load_next(Ordering::Acquire);
+            self.block = None;
+            self.enter(next_block?, 0);
+        }
+    }
 }
+
+impl<T> Drop for BlockDrain<T> {
+    #[inline]
+    fn drop(&mut self) {
+        if let Some(block) = &self.block {
+            if block.has_tombstones() {
+                for index in self.index..self.len {
+                    if !block.is_tombstone(index) {
+                        unsafe{ ptr::drop_in_place(block.mem().add(index)); }
+                    }
+                }
+                return;
+            }
+            unsafe{
+                let rest = slice::from_raw_parts_mut(block.mem().add(self.index), self.len - self.index);
+                ptr::drop_in_place(rest);
+            }
+        }
+    }
+}
+
 impl<T> Deref for BlockArc<T> {
     type Target = Block<T>;
 
@@ -221,4 +755,21 @@ impl<T> Drop for BlockArc<T> {
             Block::dec_use_count(self.ptr)
         }
     }
+}
+
+#[cfg(test)]
+mod test{
+    use crate::block::{parse_usize, Block, BLOCK_SIZE, MAX_BLOCK_BYTES};
+
+    #[test]
+    fn block_size_test() {
+        // Block allocation is dominated by messages.
+        assert!(Block::<[u8; 4096]>::layout(BLOCK_SIZE).size() >= 4096 * BLOCK_SIZE);
+        assert!(Block::<u64>::layout(BLOCK_SIZE).size() < 8 * BLOCK_SIZE + 1024);
+        assert!(Block::<u64>::layout(BLOCK_SIZE).size() <= MAX_BLOCK_BYTES);
+        assert!(Block::<u64>::layout(64).size() < 8 * 64 + 1024);
+        
+        assert_eq!(parse_usize("0"), 0);
+        assert_eq!(parse_usize("16777216"), 16 << 20);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,471 @@
+use alloc::boxed::Box;
+use alloc::sync::{Arc, Weak};
+use alloc::vec::Vec;
+use crate::sync::Mutex;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use crate::LendingReader;
+use crate::mpmc::Reader;
+
+/// How many messages [GroupMember] claims at once.
+///
+/// Larger claims mean less contention on the group's cursor. And members,
+/// that come for more often - faster ones - get more. Claims never span
+/// blocks, and never include unpublished messages - so actual claim can be
+/// smaller.
+pub trait ClaimStrategy: Send + Sync {
+    /// Size of the next claim. `previous` - size of the member's previous 
+    /// claim, 0 if there was none, or if member ran out of messages since.
+    fn claim_size(&self, previous: usize) -> usize;
+}
+
+/// Claims messages one by one. Default.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SingleClaim;
+
+impl ClaimStrategy for SingleClaim {
+    #[inline]
+    fn claim_size(&self, _previous: usize) -> usize {
+        1
+    }
+}
+
+/// Claims up to `n` messages at once.
+#[derive(Clone, Copy, Debug)]
+pub struct FixedClaim(pub usize);
+
+impl ClaimStrategy for FixedClaim {
+    #[inline]
+    fn claim_size(&self, _previous: usize) -> usize {
+        self.0
+    }
+}
+
+/// Starts with one message, and doubles claim size up to `max`, while 
+/// member keeps coming for more. Resets when member runs out of messages.
+#[derive(Clone, Copy, Debug)]
+pub struct GrowingClaim {
+    pub max: usize,
+}
+
+impl ClaimStrategy for GrowingClaim {
+    #[inline]
+    fn claim_size(&self, previous: usize) -> usize {
+        (previous * 2).clamp(1, self.max)
+    }
+}
+
+/// Group of readers, that split [mpmc] queue messages between themselves.
+///
+/// Each message goes to exactly one member of the group. Members claim
+/// messages one by one, with CAS on the group's shared cursor. Message data
+/// stays in queue blocks, and is shared as usual - so other readers of the
+/// queue, and other groups, still get every message.
+///
+/// Order is kept only within the messages claimed by the same member.
+///
+/// # Commits
+///
+/// Members [commit()] when they are done with what they got. Group tracks
+/// [committed_offset()] - position, before which every message is processed.
+/// Blocks from the committed offset are kept alive, so that a new member can
+/// resume from there, with [reader_from_committed()] - commit periodically.
+///
+/// [commit()]: GroupMember::commit
+/// [committed_offset()]: Self::committed_offset
+/// [reader_from_committed()]: Self::reader_from_committed
+///
+/// ```
+/// # use chute::{ConsumerGroup, LendingReader};
+/// let queue = chute::mpmc::Queue::new();
+/// let group = ConsumerGroup::new(queue.reader());
+/// let mut member1 = group.member();
+/// let mut member2 = group.member();
+///
+/// queue.blocking_push(1);
+/// queue.blocking_push(2);
+/// assert_eq!(member2.next(), Some(&1));
+/// assert_eq!(member1.next(), Some(&2));
+/// assert_eq!(member1.next(), None);
+/// ```
+///
+/// [mpmc]: crate::mpmc
+pub struct ConsumerGroup<T> {
+    /// Position of the next unclaimed message.
+    cursor: AtomicUsize,
+    /// Keeps blocks alive for the new members. Follows committed offset lazily.
+    tail: Mutex<Reader<T>>,
+    /// Members' committed positions.
+    members: Mutex<Vec<Weak<AtomicUsize>>>,
+    /// Last known committed offset.
+    committed: AtomicUsize,
+    /// Smallest committed position of members, dropped with uncommitted
+    /// messages. `usize::MAX` if none.
+    lost: AtomicUsize,
+    strategy: Box<dyn ClaimStrategy>,
+}
+
+impl<T> ConsumerGroup<T> {
+    /// Constructs group, that starts from `reader`'s position.
+    #[inline]
+    pub fn new(reader: Reader<T>) -> Arc<Self> {
+        Self::with_strategy(reader, SingleClaim)
+    }
+    
+    /// Same as [new()], but members claim messages with `strategy`.
+    /// 
+    /// [new()]: Self::new
+    pub fn with_strategy(reader: Reader<T>, strategy: impl ClaimStrategy + 'static) -> Arc<Self> {
+        // Tail is not a consumer - it should not hold back Queue::quiesce().
+        reader.state.report(usize::MAX);
+        let position = reader.position();
+        Arc::new(Self{
+            cursor: AtomicUsize::new(position),
+            tail: Mutex::new(reader),
+            members: Default::default(),
+            committed: AtomicUsize::new(position),
+            lost: AtomicUsize::new(usize::MAX),
+            strategy: Box::new(strategy),
+        })
+    }
+
+    /// New group member. Starts from the first unclaimed message.
+    /// 
+    /// Committed position of new member is its start position.
+    pub fn member(self: &Arc<Self>) -> GroupMember<T> {
+        let tail = self.tail.lock();
+        let mut reader = tail.clone();
+        let position = self.cursor.load(Ordering::Acquire);
+        reader.skip_to(position);
+        self.new_member(reader, position)
+    }
+
+    /// New group member, that starts from [committed_offset()]. 
+    /// 
+    /// Messages between committed offset and the first unclaimed one, were 
+    /// claimed by other members, but not committed. They are delivered again -
+    /// to this member. Then it continues as usual.
+    /// 
+    /// Use on restart, in place of the lost member - it takes over 
+    /// the uncommitted messages of dropped members.
+    /// 
+    /// Returns `None`, if blocks at committed offset were already released.
+    /// 
+    /// [committed_offset()]: Self::committed_offset
+    pub fn reader_from_committed(self: &Arc<Self>) -> Option<GroupMember<T>> {
+        let tail = self.tail.lock();
+        let committed = self.committed_offset();
+        if tail.position() > committed {
+            return None;
+        }
+        let mut reader = tail.clone();
+        reader.skip_to(committed);
+        let mut member = self.new_member(reader, committed);
+        member.replay_until = self.cursor.load(Ordering::Acquire);
+        self.lost.store(usize::MAX, Ordering::Release);
+        Some(member)
+    }
+    
+    fn new_member(self: &Arc<Self>, reader: Reader<T>, committed: usize) -> GroupMember<T> {
+        let committed = Arc::new(AtomicUsize::new(committed));
+        let mut members = self.members.lock();
+        if members.len() == members.capacity() {
+            // Clean up dropped members, before growing.
+            members.retain(|member| member.strong_count() != 0);
+        }
+        members.push(Arc::downgrade(&committed));
+        GroupMember{
+            reader,
+            group: self.clone(),
+            committed,
+            replay_until: 0,
+            claimed_until: 0,
+            previous_claim: 0,
+            uncommitted: false,
+        }
+    }
+    
+    /// Position, before which all messages are processed by the group.
+    /// 
+    /// That is - the smallest position, committed by live members, and 
+    /// by members dropped with uncommitted messages. Never goes back.
+    pub fn committed_offset(&self) -> usize {
+        let members = self.members.lock();
+        let lost = self.lost.load(Ordering::Acquire);
+        let min = members.iter()
+            .filter_map(|member| member.upgrade())
+            .map(|committed| committed.load(Ordering::Acquire))
+            .chain((lost != usize::MAX).then_some(lost))
+            .min();
+        match min {
+            Some(min) => self.committed.fetch_max(min, Ordering::AcqRel).max(min),
+            None => self.committed.load(Ordering::Acquire),
+        }
+    }
+
+    /// Lets go of the blocks before `position`, if nobody else is at it.
+    /// Blocks at committed offset are kept.
+    #[cold]
+    #[inline(never)]
+    fn advance_tail(&self, position: usize) {
+        if let Some(mut tail) = self.tail.try_lock() {
+            tail.skip_to(position.min(self.committed_offset()));
+        }
+    }
+}
+
+/// Member of [ConsumerGroup].
+///
+/// Returns messages, that no other member of the group got.
+///
+/// Constructed by [ConsumerGroup::member()].
+pub struct GroupMember<T> {
+    reader: Reader<T>,
+    group: Arc<ConsumerGroup<T>>,
+    /// Position, before which all messages this member got are processed.
+    committed: Arc<AtomicUsize>,
+    /// Messages before this position are read without claim.
+    replay_until: usize,
+    /// End of the current claim.
+    claimed_until: usize,
+    /// See [ClaimStrategy::claim_size()].
+    previous_claim: usize,
+    /// Got messages since the last commit.
+    uncommitted: bool,
+}
+
+impl<T> GroupMember<T> {
+    #[inline]
+    pub fn group(&self) -> &Arc<ConsumerGroup<T>> {
+        &self.group
+    }
+    
+    /// Marks all messages this member got so far as processed.
+    /// 
+    /// See [ConsumerGroup::committed_offset()].
+    /// 
+    /// Member dropped without commit, holds back committed offset, until
+    /// [ConsumerGroup::reader_from_committed()] takes over.
+    #[inline]
+    pub fn commit(&mut self) {
+        let position = if self.reader.position() < self.replay_until 
+                        || self.reader.position() < self.claimed_until 
+        {
+            // Not everything we got is processed.
+            self.reader.position()
+        } else {
+            // Everything before the cursor, that is not ours - was claimed by
+            // others. Everything after - is not claimed yet.
+            self.group.cursor.load(Ordering::Acquire)
+        };
+        self.committed.store(position, Ordering::Release);
+        self.uncommitted = false;
+    }
+}
+
+impl<T> Drop for GroupMember<T> {
+    #[inline]
+    fn drop(&mut self) {
+        if self.uncommitted {
+            let committed = self.committed.load(Ordering::Acquire);
+            self.group.lost.fetch_min(committed, Ordering::AcqRel);
+        }
+    }
+}
+
+impl<T> LendingReader for GroupMember<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<&T> {
+        if self.reader.position() < self.replay_until {
+            // Already published and claimed.
+            self.uncommitted = true;
+            return self.reader.next();
+        }
+        if self.reader.position() < self.claimed_until {
+            // Claimed run has no aborted slots.
+            return self.reader.next();
+        }
+        let cursor = &self.group.cursor;
+        let claim_size = self.group.strategy.claim_size(self.previous_claim).max(1);
+        loop {
+            // Reader never gets ahead of the cursor - everything it passed
+            // was claimed.
+            let position = cursor.load(Ordering::Acquire);
+            self.reader.skip_to(position);
+
+            // Is message at `position` published? 
+            // Aborted slots before it are claimed together with it.
+            if self.reader.next().is_none() {
+                self.previous_claim = 0;
+                return None;
+            }
+            self.reader.unread();
+            
+            let (_, ready) = self.reader.current_block_slice();
+            let claimed_until = self.reader.position() + claim_size.min(ready);
+            if cursor.compare_exchange_weak(
+                position, claimed_until, Ordering::AcqRel, Ordering::Acquire
+            ).is_ok() {
+                self.claimed_until = claimed_until;
+                break;
+            }
+        }
+        self.previous_claim = claim_size;
+        self.uncommitted = true;
+        if self.reader.index == 0 {
+            // Exactly one member claims each block's first message.
+            self.group.advance_tail(self.reader.position());
+        }
+        self.reader.next()
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use std::collections::HashSet;
+    use std::sync::Arc;
+    use std::thread;
+    use crate::block::BLOCK_SIZE;
+    use crate::{ConsumerGroup, GrowingClaim, LendingReader};
+    use crate::mpmc::Queue;
+
+    #[test]
+    fn member_test() {
+        let queue: Arc<Queue<usize>> = Default::default();
+        let group = ConsumerGroup::new(queue.reader());
+        let mut member1 = group.member();
+        for i in 0..BLOCK_SIZE+10 {
+            queue.blocking_push(i);
+        }
+        for i in 0..BLOCK_SIZE+5 {
+            assert_eq!(member1.next(), Some(&i));
+        }
+        // Late member starts from the first unclaimed.
+        let mut member2 = group.member();
+        assert_eq!(member2.next(), Some(&(BLOCK_SIZE+5)));
+        assert_eq!(member1.next(), Some(&(BLOCK_SIZE+6)));
+
+        // Plain readers get everything.
+        let mut reader = queue.reader();
+        queue.blocking_push(100);
+        assert_eq!(reader.next(), Some(&100));
+    }
+
+    #[test]
+    fn commit_test() {
+        let queue: Arc<Queue<usize>> = Default::default();
+        let group = ConsumerGroup::new(queue.reader());
+        let mut member1 = group.member();
+        let mut member2 = group.member();
+        for i in 0..BLOCK_SIZE*3 {
+            queue.blocking_push(i);
+        }
+        
+        for _ in 0..BLOCK_SIZE { member1.next(); }
+        member1.commit();
+        // member2 did not commit yet.
+        assert_eq!(group.committed_offset(), 0);
+        
+        for _ in 0..BLOCK_SIZE { member2.next(); }
+        member2.commit();
+        assert_eq!(group.committed_offset(), BLOCK_SIZE);
+        
+        // member1 "crashes" with uncommitted messages.
+        for _ in 0..10 { member1.next(); }
+        drop(member1);
+        member2.next();
+        member2.commit();
+        assert_eq!(group.committed_offset(), BLOCK_SIZE);
+        
+        // Replacement gets them again.
+        let mut member3 = group.reader_from_committed().unwrap();
+        for i in BLOCK_SIZE..BLOCK_SIZE*2+11 {
+            assert_eq!(member3.next(), Some(&i));
+        }
+        member3.commit();
+        assert_eq!(group.committed_offset(), BLOCK_SIZE*2+11);
+    }
+
+    #[test]
+    fn claim_strategy_test() {
+        let queue: Arc<Queue<usize>> = Default::default();
+        let group = ConsumerGroup::with_strategy(queue.reader(), GrowingClaim{max: 4});
+        let mut member1 = group.member();
+        let mut member2 = group.member();
+        for i in 0..BLOCK_SIZE+2 {
+            queue.blocking_push(i);
+        }
+        
+        // Claim sizes grow per member: 1, 2, 4, 4 ...
+        assert_eq!(member1.next(), Some(&0));
+        assert_eq!(member1.next(), Some(&1));
+        assert_eq!(member2.next(), Some(&3));
+        assert_eq!(member1.next(), Some(&2));
+        assert_eq!(member1.next(), Some(&4));
+        assert_eq!(member2.next(), Some(&8));
+        
+        // Unread claimed messages are not committed.
+        member1.commit();
+        member2.commit();
+        assert_eq!(group.committed_offset(), 5);
+        
+        // Claim does not span blocks.
+        let mut member3 = group.member();
+        let mut count = 0;
+        while let Some(&i) = member3.next() {
+            assert!(i >= 10);
+            count += 1;
+            if i == BLOCK_SIZE-1 { break; }
+        }
+        assert_eq!(count, BLOCK_SIZE-10);
+        assert_eq!(member2.next(), Some(&9));       // still in its claim
+        assert_eq!(member1.next(), Some(&5));
+        assert_eq!(member3.next(), Some(&BLOCK_SIZE));
+    }
+
+    #[test]
+    fn mt_test() {
+        const COUNT: usize = BLOCK_SIZE * 8;
+        const MEMBERS: usize = 4;
+
+        let queue: Arc<Queue<usize>> = Default::default();
+        let group = ConsumerGroup::new(queue.reader());
+
+        let mut joins = Vec::new();
+        for _ in 0..MEMBERS {
+            let mut member = group.member();
+            joins.push(thread::spawn(move || {
+                let mut claimed = Vec::new();
+                loop {
+                    match member.next() {
+                        Some(&value) => {
+                            if value == usize::MAX {
+                                return claimed;
+                            }
+                            claimed.push(value);
+                        }
+                        None => thread::yield_now(),
+                    }
+                }
+            }));
+        }
+
+        for i in 0..COUNT {
+            queue.blocking_push(i);
+        }
+        for _ in 0..MEMBERS {
+            queue.blocking_push(usize::MAX);
+        }
+
+        let mut all = HashSet::new();
+        for join in joins {
+            let claimed = join.join().unwrap();
+            // In order within member.
+            assert!(claimed.windows(2).all(|w| w[0] < w[1]));
+            for value in claimed {
+                assert!(all.insert(value));
+            }
+        }
+        assert_eq!(all, (0..COUNT).collect());
+    }
+}
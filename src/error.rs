@@ -0,0 +1,29 @@
+use core::fmt;
+
+/// Error of fallible chute operations.
+/// 
+/// Operations that can be simply retried, like pushing to a full buffer,
+/// return the value back instead.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[non_exhaustive]
+pub enum Error {
+    /// Operation on readers that belong to different queues.
+    DifferentQueues,
+    /// Reader can not reach the offset - it is behind the reader, or past
+    /// published messages.
+    OffsetUnavailable,
+    /// Queue is closed, and reader has read everything.
+    Closed,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::DifferentQueues => f.write_str("readers belong to different queues"),
+            Error::OffsetUnavailable => f.write_str("offset is not reachable by reader"),
+            Error::Closed => f.write_str("queue is closed"),
+        }
+    }
+}
+
+impl core::error::Error for Error {}
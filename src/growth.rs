@@ -0,0 +1,101 @@
+#[cfg(feature = "std")]
+use core::cmp;
+#[cfg(feature = "std")]
+use core::sync::atomic::{AtomicU64, Ordering};
+#[cfg(feature = "std")]
+use std::time::Instant;
+
+/// First block capacity for adaptive queue.
+#[cfg(feature = "std")]
+pub(crate) const MIN_ADAPTIVE_CAPACITY: usize = 64;
+
+/// Previous block filled faster than this - next one grows.
+#[cfg(feature = "std")]
+const GROW_NANOS: u64 = 1_000_000;
+/// Previous block filled slower than this - next one shrinks.
+#[cfg(feature = "std")]
+const SHRINK_NANOS: u64 = 100_000_000;
+
+/// Adaptive block capacity, based on message rate.
+///
+/// Next block capacity is doubled if the previous block was filled fast,
+/// and halved if slow. Within `MIN_ADAPTIVE_CAPACITY..=max`.
+#[cfg(feature = "std")]
+pub(crate) struct AdaptiveCapacity {
+    epoch: Instant,
+    /// Nanoseconds since `epoch` of the last block allocation.
+    last_alloc: AtomicU64,
+}
+
+#[cfg(feature = "std")]
+impl Default for AdaptiveCapacity {
+    #[inline]
+    fn default() -> Self {
+        Self{
+            epoch: Instant::now(),
+            last_alloc: AtomicU64::new(0),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl AdaptiveCapacity {
+    /// Capacity for the block, that follows block with `current` capacity.
+    ///
+    /// Called on block insert, under queue's write lock.
+    pub fn next_capacity(&self, current: usize, max: usize) -> usize {
+        let now = self.epoch.elapsed().as_nanos() as u64;
+        let elapsed = now.saturating_sub(self.last_alloc.swap(now, Ordering::Relaxed));
+        if elapsed < GROW_NANOS {
+            cmp::min(current * 2, max)
+        } else if elapsed > SHRINK_NANOS {
+            cmp::max(current / 2, cmp::min(MIN_ADAPTIVE_CAPACITY, max))
+        } else {
+            current
+        }
+    }
+}
+
+/// Queue block capacity policy.
+pub(crate) struct BlockCapacity {
+    #[cfg(feature = "std")]
+    adaptive: Option<AdaptiveCapacity>,
+    /// Queue block size.
+    max: usize,
+}
+
+impl BlockCapacity {
+    #[inline]
+    pub fn new(max: usize) -> Self {
+        Self{ 
+            #[cfg(feature = "std")]
+            adaptive: None, 
+            max 
+        }
+    }
+    
+    #[cfg(feature = "std")]
+    #[inline]
+    pub fn set_adaptive(&mut self) {
+        self.adaptive = Some(Default::default());
+    }
+
+    #[inline]
+    pub fn first(&self) -> usize {
+        #[cfg(feature = "std")]
+        if self.adaptive.is_some() { 
+            return cmp::min(MIN_ADAPTIVE_CAPACITY, self.max);
+        }
+        self.max
+    }
+
+    #[inline]
+    #[cfg_attr(not(feature = "std"), allow(unused_variables))]
+    pub fn next(&self, current: usize) -> usize {
+        #[cfg(feature = "std")]
+        if let Some(adaptive) = &self.adaptive {
+            return adaptive.next_capacity(current, self.max);
+        }
+        self.max
+    }
+}
@@ -0,0 +1,101 @@
+use alloc::sync::Arc;
+use crate::mpmc::{Queue, Reader, Writer};
+
+/// Producer side of [mpmc] queue, for passing around.
+///
+/// Clone is a new producer of the same queue.
+///
+/// ```
+/// # use chute::{ConsumerFactory, LendingReader};
+/// let consumers = ConsumerFactory::new(chute::mpmc::Queue::new());
+/// let mut reader = consumers.reader();
+///
+/// let mut producer1 = consumers.producer();
+/// let mut producer2 = producer1.clone();
+/// producer1.push(1);
+/// producer2.push(2);
+///
+/// assert_eq!(reader.next(), Some(&1));
+/// assert_eq!(reader.next(), Some(&2));
+/// ```
+///
+/// [mpmc]: crate::mpmc
+pub struct ProducerHandle<T>{
+    writer: Writer<T>,
+}
+
+impl<T> ProducerHandle<T> {
+    #[inline]
+    pub fn new(queue: &Arc<Queue<T>>) -> Self {
+        Self{ writer: queue.writer() }
+    }
+
+    /// Push value, and wake blocked readers. See [Writer::push()].
+    #[inline]
+    pub fn push(&mut self, value: T) {
+        self.writer.push(value)
+    }
+
+    /// Factory of this queue's readers.
+    #[inline]
+    pub fn consumers(&self) -> ConsumerFactory<T> {
+        ConsumerFactory{ queue: self.writer.event_queue.clone() }
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> Writer<T> {
+        self.writer
+    }
+}
+
+impl<T> Clone for ProducerHandle<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self::new(&self.writer.event_queue)
+    }
+}
+
+/// Consumer side of [mpmc] queue, for passing around.
+///
+/// Cheap to clone. Constructs readers with [reader()], and producers
+/// with [producer()].
+///
+/// [mpmc]: crate::mpmc
+/// [reader()]: Self::reader
+/// [producer()]: Self::producer
+pub struct ConsumerFactory<T>{
+    queue: Arc<Queue<T>>,
+}
+
+impl<T> ConsumerFactory<T> {
+    #[inline]
+    pub fn new(queue: Arc<Queue<T>>) -> Self {
+        Self{ queue }
+    }
+
+    /// New subscriber. Receives messages pushed from now on.
+    /// See [Queue::reader()].
+    #[must_use]
+    #[inline]
+    pub fn reader(&self) -> Reader<T> {
+        self.queue.reader()
+    }
+
+    #[must_use]
+    #[inline]
+    pub fn producer(&self) -> ProducerHandle<T> {
+        ProducerHandle::new(&self.queue)
+    }
+
+    #[inline]
+    pub fn queue(&self) -> &Arc<Queue<T>> {
+        &self.queue
+    }
+}
+
+impl<T> Clone for ConsumerFactory<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self{ queue: self.queue.clone() }
+    }
+}
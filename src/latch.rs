@@ -0,0 +1,77 @@
+use alloc::sync::Arc;
+use crate::subscriptions::Subscriptions;
+
+/// Reader count-down latch.
+///
+/// Released when the required number of readers subscribed to the queue
+/// after latch construction. Use it to make sure that all readers are in place,
+/// before the producer starts pushing messages - otherwise late readers
+/// will miss early messages.
+///
+/// Only readers constructed by `Queue::reader()` are counted.
+/// Reader clones are not.
+///
+/// Constructed by [spmc::Queue::barrier()] and [mpmc::Queue::barrier()].
+///
+/// [spmc::Queue::barrier()]: crate::spmc::Queue::barrier
+/// [mpmc::Queue::barrier()]: crate::mpmc::Queue::barrier
+pub struct ReaderLatch {
+    subscriptions: Arc<Subscriptions>,
+    target: usize,
+}
+
+impl ReaderLatch {
+    #[inline]
+    pub(crate) fn new(subscriptions: Arc<Subscriptions>, n: usize) -> Self {
+        let target = subscriptions.count() + n;
+        Self{subscriptions, target}
+    }
+
+    /// Number of readers still expected.
+    #[inline]
+    pub fn remaining(&self) -> usize {
+        self.target.saturating_sub(self.subscriptions.count())
+    }
+
+    #[inline]
+    pub fn is_released(&self) -> bool {
+        self.remaining() == 0
+    }
+
+    /// Blocks current thread until latch is released.
+    #[inline]
+    pub fn wait(&self) {
+        self.subscriptions.wait_count(self.target);
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use crate::{mpmc, spmc};
+
+    #[test]
+    fn latch_test() {
+        let queue: std::sync::Arc<mpmc::Queue<usize>> = mpmc::Queue::new();
+        let _early_reader = queue.reader();
+
+        let latch = queue.barrier(4);
+        assert_eq!(latch.remaining(), 4);
+
+        std::thread::scope(|s| {
+            for _ in 0..4 {
+                s.spawn(|| {
+                    let _reader = queue.reader();
+                });
+            }
+            latch.wait();
+        });
+        assert!(latch.is_released());
+
+        let queue: spmc::Queue<usize> = spmc::Queue::new();
+        let latch = queue.barrier(1);
+        let reader = queue.reader();
+        let _clone = reader.clone();
+        latch.wait();
+        assert_eq!(queue.barrier(0).remaining(), 0);
+    }
+}
@@ -19,6 +19,8 @@
 //!
 //! The read performance is almost equal, with a slight advantage for [spmc]. 
 //! 
+//! For the one-to-one case there is dedicated [spsc]. 
+//! 
 //! # Order
 //! 
 //! [spmc] is fully ordered. 
@@ -33,28 +35,128 @@
 //! All readers have something like Arc for its current block in shared queue.
 //! This means that each reader prevents an unread portion of a queue from being dropped.
 //! 
+//! # Block size
+//! 
+//! Queue allocates memory in blocks of 4096 messages. So block allocation size 
+//! is `4096 * size_of::<T>()` plus a small header - 16MiB for a 4KiB `T`!
+//! Box large messages, or use smaller blocks - block size is `Queue`'s 
+//! second generic parameter: `spmc::Queue<T, 64>`.
+//! 
+//! As a guardrail, using `T` with block size over 64MiB is a compile-time error.
+//! Limit can be changed with `CHUTE_MAX_BLOCK_BYTES` environment variable, at compile time.
+//! 
+//! ```compile_fail
+//! let queue = chute::spmc::Queue::<[u8; 1 << 16]>::new();
+//! ```
+//! 
+//! # Pinning
+//! 
+//! Pushed messages never move. They live in heap-allocated blocks, and 
+//! are dropped in place when the block is freed. So readers can return pinned
+//! references with `next_pinned()`, and `!Unpin` types (like futures or 
+//! intrusive structures) can be stored in the queue.
+//! 
+//! Ways to move messages out - `Reader::into_drain()` and 
+//! `spmc::Queue::take_full_block()` - require `T: Unpin`.
+//! 
+//! # Memory ordering
+//! 
+//! Push is a release operation, and reading the message is an acquire one.
+//! So everything the writer did before push, is visible to the reader
+//! after it got the message - [current_block_slice()] included.
+//! 
+//! For custom protocols on top of that - for example, reader signaling back 
+//! through relaxed atomics - there are `Queue::release_fence()` and
+//! `Reader::acquire_fence()`.
+//! 
+//! [current_block_slice()]: mpmc::Reader::current_block_slice
+//! 
 //! # target-flags
 //! 
 //! [mpmc] use [trailing_ones()]. So you want to have hardware support for it.
 //! On x86 you need `BMI1`, there is analog on each cpu architecture.
 //!
 //! [trailing_ones()]: u64::trailing_ones 
+//! 
+//! # no_std
+//! 
+//! With default `std` feature off, chute is `no_std` - it needs only `alloc`.
+//! Then there is no thread parking: blocking reader API (`Reader::wait()`, 
+//! `Reader::recv()`) is not available, and the rest of waits - like pushing
+//! to a bounded queue - spin. Adaptive blocks and [StdClock] need `std` as well.
+
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+#![cfg_attr(feature = "allocator_api", feature(allocator_api))]
+
+extern crate alloc;
 
 mod block;
+pub use block::BlockInfo;
 
 pub mod mpmc;
 pub mod spmc;
+pub mod spsc;
 
 mod reader;
 pub use reader::*;
 
+mod time_batch;
+pub use time_batch::*;
+
+mod batch;
+pub use batch::*;
+
+mod error;
+pub use error::Error;
+
+mod growth;
+mod metrics;
+#[cfg(feature = "metrics")]
+pub use metrics::{Metrics, ReaderDiagnostics};
+mod sync;
+mod notify;
+mod subscriptions;
+mod latch;
+pub use latch::ReaderLatch;
+mod subscription;
+pub use subscription::Subscription;
+mod read_guard;
+pub use read_guard::ReadGuard;
+mod consumer_group;
+pub use consumer_group::{ClaimStrategy, ConsumerGroup, FixedClaim, GroupMember, GrowingClaim, SingleClaim};
+mod handle;
+pub use handle::{ConsumerFactory, ProducerHandle};
+#[cfg(feature = "async")]
+mod stream;
+#[cfg(feature = "async")]
+pub use stream::ReaderStream;
+
 #[cfg(test)]
 mod test{
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    
+    /// Waker, that counts wakes.
+    #[cfg(feature = "async")]
+    #[derive(Default)]
+    pub struct CountingWaker {
+        pub wakes: AtomicUsize,
+    }
+    #[cfg(feature = "async")]
+    impl std::task::Wake for CountingWaker {
+        fn wake(self: std::sync::Arc<Self>) {
+            self.wake_by_ref()
+        }
+        fn wake_by_ref(self: &std::sync::Arc<Self>) {
+            self.wakes.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+    
     #[derive(Clone, PartialEq)]
     pub struct StringWrapper(String);
     impl From<usize> for StringWrapper{
         fn from(value: usize) -> Self {
-            Self(String::from(format!("{value}")))
+            Self(format!("{value}"))
         }
     }
     impl From<StringWrapper> for usize{
@@ -62,4 +164,55 @@ mod test{
             value.0.parse().unwrap()
         }
     }    
+    
+    /// Counts allocations and deallocations.
+    #[derive(Default)]
+    pub struct CountingAllocator{
+        pub allocs  : AtomicUsize,
+        pub deallocs: AtomicUsize,
+    }
+    unsafe impl GlobalAlloc for CountingAllocator{
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            self.allocs.fetch_add(1, Ordering::Relaxed);
+            System.alloc(layout)
+        }
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            self.deallocs.fetch_add(1, Ordering::Relaxed);
+            System.dealloc(ptr, layout)
+        }
+    }
+    
+    /// Counts freed allocations, that still contain `[PATTERN; 32]`.
+    #[derive(Default)]
+    pub struct ScanningAllocator{
+        pub deallocs: AtomicUsize,
+        pub dirty   : AtomicUsize,
+    }
+    impl ScanningAllocator{
+        pub const PATTERN: u8 = 0xA5;
+    }
+    unsafe impl GlobalAlloc for ScanningAllocator{
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            System.alloc(layout)
+        }
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            let bytes = std::slice::from_raw_parts(ptr, layout.size());
+            if bytes.windows(32).any(|w| w.iter().all(|&b| b == Self::PATTERN)) {
+                self.dirty.fetch_add(1, Ordering::Relaxed);
+            }
+            self.deallocs.fetch_add(1, Ordering::Relaxed);
+            System.dealloc(ptr, layout)
+        }
+    }
+    #[cfg(feature = "allocator_api")]
+    unsafe impl std::alloc::Allocator for CountingAllocator{
+        fn allocate(&self, layout: Layout) -> Result<std::ptr::NonNull<[u8]>, std::alloc::AllocError> {
+            self.allocs.fetch_add(1, Ordering::Relaxed);
+            System.allocate(layout)
+        }
+        unsafe fn deallocate(&self, ptr: std::ptr::NonNull<u8>, layout: Layout) {
+            self.deallocs.fetch_add(1, Ordering::Relaxed);
+            System.deallocate(ptr, layout)
+        }
+    }
 }
\ No newline at end of file
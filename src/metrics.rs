@@ -0,0 +1,126 @@
+//! Writer contention and reader overhead counters.
+//!
+//! Counted only with `metrics` feature - otherwise counting is a no-op.
+
+#[cfg(feature = "metrics")]
+use core::sync::atomic::{AtomicU64, Ordering};
+
+/// Snapshot of [mpmc::Queue] writer counters.
+///
+/// Frequent `full_block_pushes` mean writers race at block boundaries.
+///
+/// Constructed by [mpmc::Queue::metrics()].
+///
+/// [mpmc::Queue]: crate::mpmc::Queue
+/// [mpmc::Queue::metrics()]: crate::mpmc::Queue::metrics
+#[cfg(feature = "metrics")]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Metrics {
+    /// Push attempts, that found the block full, and took the slow path.
+    pub full_block_pushes: u64,
+    /// Blocks allocated by writers.
+    pub blocks_allocated: u64,
+    /// Slow path entries, that found a non-full block - already inserted
+    /// by another writer.
+    pub blocks_found: u64,
+    /// `Writer::update()` calls, that found writer too far behind to walk 
+    /// the block chain - and loaded the last block from the queue.
+    pub writer_update_fallbacks: u64,
+}
+
+#[derive(Default)]
+pub(crate) struct Counters {
+    #[cfg(feature = "metrics")]
+    full_block_pushes: AtomicU64,
+    #[cfg(feature = "metrics")]
+    blocks_allocated: AtomicU64,
+    #[cfg(feature = "metrics")]
+    blocks_found: AtomicU64,
+    #[cfg(feature = "metrics")]
+    writer_update_fallbacks: AtomicU64,
+}
+
+impl Counters {
+    #[inline]
+    pub fn full_block_push(&self) {
+        #[cfg(feature = "metrics")]
+        self.full_block_pushes.fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[inline]
+    pub fn block_allocated(&self) {
+        #[cfg(feature = "metrics")]
+        self.blocks_allocated.fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[inline]
+    pub fn block_found(&self) {
+        #[cfg(feature = "metrics")]
+        self.blocks_found.fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[inline]
+    pub fn writer_update_fallback(&self) {
+        #[cfg(feature = "metrics")]
+        self.writer_update_fallbacks.fetch_add(1, Ordering::Relaxed);
+    }
+
+    #[cfg(feature = "metrics")]
+    pub fn snapshot(&self) -> Metrics {
+        Metrics{
+            full_block_pushes: self.full_block_pushes.load(Ordering::Relaxed),
+            blocks_allocated : self.blocks_allocated.load(Ordering::Relaxed),
+            blocks_found     : self.blocks_found.load(Ordering::Relaxed),
+            writer_update_fallbacks: self.writer_update_fallbacks.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// Snapshot of [mpmc::Reader] counters.
+///
+/// `bitblock_loads` per block switch shows how often reader catches
+/// writers mid-block: reader that only reads full blocks, loads each
+/// block's bitblocks once.
+///
+/// Constructed by [mpmc::Reader::diagnostics()].
+///
+/// [mpmc::Reader]: crate::mpmc::Reader
+/// [mpmc::Reader::diagnostics()]: crate::mpmc::Reader::diagnostics
+#[cfg(feature = "metrics")]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ReaderDiagnostics {
+    /// Atomic loads of block bitblocks, to find published messages.
+    pub bitblock_loads: u64,
+    /// Moves to the next block.
+    pub block_switches: u64,
+}
+
+#[derive(Default)]
+pub(crate) struct ReaderCounters {
+    #[cfg(feature = "metrics")]
+    bitblock_loads: u64,
+    #[cfg(feature = "metrics")]
+    block_switches: u64,
+}
+
+impl ReaderCounters {
+    #[inline]
+    pub fn bitblock_load(&mut self) {
+        #[cfg(feature = "metrics")]
+        { self.bitblock_loads += 1; }
+    }
+
+    #[inline]
+    pub fn block_switch(&mut self) {
+        #[cfg(feature = "metrics")]
+        { self.block_switches += 1; }
+    }
+
+    #[cfg(feature = "metrics")]
+    pub fn snapshot(&self) -> ReaderDiagnostics {
+        ReaderDiagnostics{
+            bitblock_loads: self.bitblock_loads,
+            block_switches: self.block_switches,
+        }
+    }
+}
@@ -2,24 +2,58 @@
 //! 
 //! Thread-safe lockless writers and readers.
 
-use std::marker::PhantomData;
-use std::ptr::{null_mut, NonNull};
-use std::sync::Arc;
-use std::sync::atomic::{AtomicPtr, Ordering};
+use alloc::alloc::GlobalAlloc;
+use core::cmp;
+use core::future::Future;
+use core::marker::PhantomData;
+use core::mem::ManuallyDrop;
+use core::pin::Pin;
+use core::ptr::{null_mut, NonNull};
+use core::slice;
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use crate::sync::Mutex;
+use crate::sync::{fence, AtomicPtr, Ordering};
+use core::task::{Context, Poll};
 use branch_hints::unlikely;
-use crate::block::{Block, BlockArc, BLOCK_SIZE};
-use crate::LendingReader;
+use crate::block::{Block, BlockAllocator, BlockArc, BlockDrain, BlockInfo, BLOCK_SIZE};
+use crate::growth::BlockCapacity;
+use crate::metrics::{Counters, ReaderCounters};
+use crate::subscriptions::{ReaderState, Subscriptions};
+use crate::{CopyingReader, Error, LendingReader, PeekingReader, ReadGuard, ReaderLatch, ReaderOffset, RewindableReader, Subscription};
 
-pub struct Queue<T> {
+/// `N` - block size. Multiple of 64. Smaller blocks - for large `T`, 
+/// or queues that are expected to stay small.
+/// 
+/// ```
+/// # use std::sync::Arc;
+/// let queue: Arc<chute::mpmc::Queue<[u8; 512], 64>> = Default::default();
+/// queue.blocking_push([0; 512]);
+/// ```
+pub struct Queue<T, const N: usize = BLOCK_SIZE> {
     last_block: AtomicPtr<Block<T>>,
+    subscriptions: Arc<Subscriptions>,
+    block_capacity: BlockCapacity,
+    counters: Counters,
+    on_first_overflow: Mutex<Option<Box<dyn FnOnce() + Send>>>,
+    /// Max unread messages. See [Queue::bounded()].
+    bound: Option<usize>,
     phantom_data: PhantomData<T>
 }
 
-impl<T> Default for Queue<T> {
+impl<T, const N: usize> Default for Queue<T, N> {
     #[inline]
     fn default() -> Self {
+        const { Block::<T>::check_capacity(N) };
+        let block_capacity = BlockCapacity::new(N);
         Self {
-            last_block: AtomicPtr::new(Block::<T>::new().into_raw().as_ptr()),
+            last_block: AtomicPtr::new(Block::<T>::new(block_capacity.first()).into_raw().as_ptr()),
+            subscriptions: Default::default(),
+            block_capacity,
+            counters: Default::default(),
+            on_first_overflow: Default::default(),
+            bound: None,
             phantom_data: PhantomData
         }   
     }
@@ -32,36 +66,171 @@ impl<T> Queue<T> {
         Default::default()    
     }
     
+    /// Queue, that holds at most `bound` unread messages. 
+    /// 
+    /// Writers block on push, while some reader did not read past the message
+    /// pushed `bound` messages ago. So the slowest reader limits writers - 
+    /// no message is lost.
+    /// 
+    /// Readers report their progress when they run out of messages (see
+    /// [quiesce()]), and on each block switch - so a blocked writer waits 
+    /// until readers catch up fully, or leave a block. 
+    /// A reader, that is not polled, blocks writers forever. 
+    /// 
+    /// Bound is per writer: each writer takes room from its own snapshot 
+    /// of reader progress, without coordinating with other writers. So with
+    /// `W` concurrent writers, queue can hold up to `W * bound` unread 
+    /// messages. Single writer never exceeds `bound`.
+    /// 
+    /// # Panics
+    /// 
+    /// Panics if `bound` is 0.
+    /// 
+    /// [quiesce()]: Self::quiesce
+    #[must_use]
     #[inline]
-    fn lock_last_block(&self) -> NonNull<Block<T>> {
-        loop {
-            let ptr = self.last_block.swap(null_mut(), Ordering::Acquire);
-            if let Some(ptr) = NonNull::new(ptr) {
-                break ptr
+    pub fn bounded(bound: usize) -> Arc<Self> {
+        assert!(bound != 0, "Queue bound should be positive.");
+        let mut queue = Self::default();
+        queue.bound = Some(bound);
+        Arc::new(queue)
+    }
+}
+
+impl<T, const N: usize> Queue<T, N> {
+    /// Overwrite freed block memory with zeros, after dropping messages.
+    /// 
+    /// Use it for sensitive data, that should not linger in freed heap.
+    /// Off by default.
+    /// 
+    /// ```
+    /// # use std::sync::Arc;
+    /// # use chute::mpmc::Queue;
+    /// let queue: Arc<Queue<String>> = Arc::new(Queue::default().with_zeroize(true));
+    /// ```
+    #[must_use]
+    #[inline]
+    pub fn with_zeroize(self, zeroize: bool) -> Self {
+        let last_block = unsafe{ &*self.last_block.load(Ordering::Relaxed) };
+        last_block.zeroize.store(zeroize, Ordering::Relaxed);
+        self
+    }
+    
+    /// Touch memory of each new block on allocation, so the first writes 
+    /// to the block do not page-fault. Removes page-fault jitter from 
+    /// latency-critical writer, at the cost of allocation time.
+    /// Off by default.
+    #[must_use]
+    #[inline]
+    pub fn with_prefault(self, prefault: bool) -> Self {
+        self.load_last_block().set_prefault(prefault);
+        self
+    }
+    
+    /// Adapt block capacity to message rate.
+    /// 
+    /// See [spmc::Queue::with_adaptive_blocks()].
+    /// 
+    /// [spmc::Queue::with_adaptive_blocks()]: crate::spmc::Queue::with_adaptive_blocks
+    #[cfg(feature = "std")]
+    #[must_use]
+    #[inline]
+    pub fn with_adaptive_blocks(mut self) -> Self {
+        self.block_capacity.set_adaptive();
+        
+        // Replace first block with a small one, if untouched.
+        let last_block = unsafe{ &*self.last_block.load(Ordering::Relaxed) };
+        self.replace_first_block(self.block_capacity.first(), last_block.allocator.clone());
+        self
+    }
+    
+    /// Allocate and free block memory with `allocator`, instead of the global
+    /// one. For example, to place blocks on a specific NUMA node.
+    /// 
+    /// Should be called on a fresh queue - blocks are allocated in advance. 
+    /// 
+    /// # Panics
+    /// 
+    /// Panics if queue already has messages, readers or writers.
+    /// 
+    /// ```
+    /// # use std::alloc::System;
+    /// # use std::sync::Arc;
+    /// # use chute::mpmc::Queue;
+    /// let queue: Arc<Queue<usize>> = Arc::new(
+    ///     Queue::default().with_block_allocator(Arc::new(System))
+    /// );
+    /// ```
+    #[must_use]
+    #[inline]
+    pub fn with_block_allocator(mut self, allocator: Arc<dyn GlobalAlloc + Send + Sync>) -> Self {
+        let capacity = unsafe{ &*self.last_block.load(Ordering::Relaxed) }.capacity;
+        assert!(
+            self.replace_first_block(capacity, Some(allocator)), 
+            "Queue already has messages, readers or writers."
+        );
+        self
+    }
+    
+    /// Same as [with_block_allocator()], but with [Allocator].
+    /// 
+    /// See [spmc::Queue::with_allocator()].
+    /// 
+    /// [with_block_allocator()]: Self::with_block_allocator
+    /// [Allocator]: core::alloc::Allocator
+    /// [spmc::Queue::with_allocator()]: crate::spmc::Queue::with_allocator
+    #[cfg(feature = "allocator_api")]
+    #[must_use]
+    #[inline]
+    pub fn with_allocator<A>(self, allocator: A) -> Self
+    where
+        A: core::alloc::Allocator + Send + Sync + 'static
+    {
+        self.with_block_allocator(Arc::new(crate::block::AllocatorAdapter(allocator)))
+    }
+    
+    /// Replaces the first block with a new one, if untouched. 
+    /// Returns `false` if block is in use.
+    fn replace_first_block(&mut self, capacity: usize, allocator: Option<BlockAllocator>) -> bool {
+        let last_block = unsafe{ &*self.last_block.load(Ordering::Relaxed) };
+        if last_block.seq == 0 && last_block.is_unique() && last_block.len.load(Ordering::Relaxed) == 0 {
+            let new_block = last_block.new_first(capacity, allocator);
+            let old_block = self.last_block.swap(new_block.into_raw().as_ptr(), Ordering::Relaxed);
+            unsafe{
+                Block::dec_use_count(NonNull::new_unchecked(old_block));
             }
+            true
+        } else {
+            false
         }
     }
     
     #[inline]
-    fn unlock_last_block(&self, ptr: NonNull<Block<T>>) {
-        self.last_block.store(ptr.as_ptr(), Ordering::Release);
+    fn try_lock_last_block(&self) -> Option<LastBlockLock<'_, T, N>> {
+        let block = NonNull::new(self.last_block.swap(null_mut(), Ordering::Acquire))?;
+        Some(LastBlockLock{ queue: self, block })
+    }
+    
+    #[inline]
+    fn lock_last_block(&self) -> LastBlockLock<'_, T, N> {
+        loop {
+            if let Some(lock) = self.try_lock_last_block() {
+                break lock
+            }
+        }
     }
     
     #[must_use]
     #[inline]
     fn load_last_block(&self) -> BlockArc<T> {
         // fetch ptr and "lock"
-        let ptr = self.lock_last_block();
-        
-        let arc = unsafe {
-            Block::inc_use_count(ptr);
-            BlockArc::from_raw(ptr)
-        };
-        
-        // release "lock"
-        self.unlock_last_block(ptr);
+        let lock = self.lock_last_block();
         
-        arc
+        unsafe {
+            Block::inc_use_count(lock.block);
+            BlockArc::from_raw(lock.block)
+        }
+        // release "lock" on drop
     }
     
     /// Returns (latest block, inserted). 
@@ -73,10 +242,11 @@ impl<T> Queue<T> {
     #[inline]
     fn insert_block(&self) -> (BlockArc<T>, bool) {
         // 1. Lock
-        let last_block = self.lock_last_block();
+        let mut lock = self.lock_last_block();
+        let last_block = lock.block;
         let last_block_ref = unsafe{ last_block.as_ref() };
         
-        if last_block_ref.len.load(Ordering::Acquire) < BLOCK_SIZE {
+        if last_block_ref.len.load(Ordering::Acquire) < last_block_ref.capacity {
             // Arc counter ++
             let arc = unsafe { 
                 Block::inc_use_count(last_block);
@@ -84,29 +254,103 @@ impl<T> Queue<T> {
             };
             
             // unlock
-            self.unlock_last_block(last_block);
+            drop(lock);
             
+            self.counters.block_found();
             return (arc, false);
         }
+        self.counters.block_allocated();
         
         // 2. Make new block
-        //    +1 counter for EventQueue::last_block (written on unlock_last_block)
+        //    +1 counter for EventQueue::last_block (written on unlock)
         //    +1 counter for Block::next
         //    +1 counter for returned BlockArc 
-        let new_block = Block::with_counter(3).into_raw();
+        let capacity  = self.block_capacity.next(last_block_ref.capacity);
+        let new_block = last_block_ref.new_next(3, capacity).into_raw();
 
-        // 3. Connect new block with old
-        last_block_ref.next.store(new_block.as_ptr(), Ordering::Release);
+        // 3. Connect new block with old, and set it as last.
+        last_block_ref.next.store(new_block.as_ptr(), Ordering::SeqCst); // SeqCst for Notifier.
+        lock.block = new_block;
         
         // 4. Arc -- old block
         unsafe{
             Block::dec_use_count(last_block);
         }
         
-        // 5. Set new block as last, and release lock.
-        self.unlock_last_block(new_block);
-
-        (unsafe{ BlockArc::from_raw(new_block) }, true)
+        // 5. Release lock.
+        drop(lock);
+        
+        let new_block = unsafe{ BlockArc::from_raw(new_block) };
+        self.block_inserted(&new_block);
+        (new_block, true)
+    }
+    
+    /// Reserves `n` contiguous slots at the queue end. 
+    /// 
+    /// Returns (block, first reserved index, next block). If reserved range
+    /// does not fit in block, it continues at the start of the next block,
+    /// that is inserted for it. `n` must not exceed block size.
+    /// 
+    /// Blocking.
+    #[cold]
+    #[inline(never)]
+    fn reserve_group(&self, n: usize) -> (BlockArc<T>, usize, Option<BlockArc<T>>) {
+        // 1. Lock
+        let mut lock = self.lock_last_block();
+        let last_block = lock.block;
+        let last_block_ref = unsafe{ last_block.as_ref() };
+        let capacity = last_block_ref.capacity;
+        
+        // 2. Reserve the rest of block, or range within it.
+        let mut len = last_block_ref.len.load(Ordering::Acquire);
+        let index = loop {
+            let new_len = if len + n <= capacity { len + n } else { cmp::max(len, capacity) };
+            match last_block_ref.len.compare_exchange_weak(len, new_len, Ordering::AcqRel, Ordering::Acquire) {
+                Ok(_) => break len,
+                Err(actual) => len = actual,
+            }
+        };
+        
+        // Arc counter ++
+        let block = unsafe { 
+            Block::inc_use_count(last_block);
+            BlockArc::from_raw(last_block)
+        };
+        
+        if index + n <= capacity {
+            drop(lock);
+            return (block, index, None);
+        }
+        
+        // 3. Make new block, with the rest of range reserved.
+        //    +1 counter for EventQueue::last_block (written on unlock)
+        //    +1 counter for Block::next
+        //    +1 counter for returned BlockArc
+        self.counters.block_allocated();
+        let rest = n - capacity.saturating_sub(index);
+        let new_capacity = cmp::max(
+            self.block_capacity.next(capacity), 
+            rest.next_multiple_of(64)
+        );
+        let new_block = last_block_ref.new_next(3, new_capacity);
+        new_block.len.store(rest, Ordering::Relaxed);
+        let new_block = new_block.into_raw();
+        
+        // 4. Connect new block with old, and set it as last.
+        last_block_ref.next.store(new_block.as_ptr(), Ordering::SeqCst); // SeqCst for Notifier.
+        lock.block = new_block;
+        
+        // 5. Arc -- old block
+        unsafe{
+            Block::dec_use_count(last_block);
+        }
+        
+        // 6. Release lock.
+        drop(lock);
+        
+        let new_block = unsafe{ BlockArc::from_raw(new_block) };
+        self.block_inserted(&new_block);
+        (block, index, Some(new_block))
     }
     
     /// Push value to queue.
@@ -121,25 +365,52 @@ impl<T> Queue<T> {
     /// Use it if you need to occasionally push a single value.
     #[inline]
     pub fn blocking_push(&self, value: T) {
+        if self.bound.is_some() {
+            self.wait_room(self.write_position(), 1);
+        }
+        
         // 1. Lock
-        let block = self.lock_last_block();
-        if let Err(value) = unsafe{ block.as_ref() }.try_push(value) {
+        let lock = self.lock_last_block();
+        self.push_and_unlock(lock, value);
+    }
+    
+    /// Same as [blocking_push()], but instead of spinning on the contended 
+    /// lock - returns `Pending`, and retries on the next poll.
+    /// 
+    /// Completes on the first poll, if there is no contention.
+    /// 
+    /// [blocking_push()]: Self::blocking_push
+    #[inline]
+    pub fn push_async(&self, value: T) -> PushAsync<'_, T, N> {
+        PushAsync{ queue: self, value: Some(value) }
+    }
+    
+    /// Push to the locked last block, and unlock.
+    #[inline]
+    fn push_and_unlock(&self, lock: LastBlockLock<'_, T, N>, value: T) {
+        if let Err(value) = unsafe{ Block::try_push(lock.block, value) } {
             #[cold]
             #[inline(never)]
-            fn insert_block_and_push<T>(this: &Queue<T>, last_block: &Block<T>, value: T){
-                let mut new_block = {
+            fn insert_block_and_push<T, const N: usize>(this: &Queue<T, N>, mut lock: LastBlockLock<'_, T, N>, value: T){
+                this.counters.full_block_push();
+                this.counters.block_allocated();
+                let last_block = lock.block;
+                let last_block_ref = unsafe{ last_block.as_ref() };
+                let new_block = {
                     // 2. Make new block
-                    //    +1 counter for EventQueue::last_block (written on unlock_last_block)
+                    //    +1 counter for EventQueue::last_block (written on unlock)
                     //    +1 counter for Block::next
                     //    +1 counter for returned BlockArc 
-                    let new_block = Block::with_counter(3).into_raw();
+                    let capacity  = this.block_capacity.next(last_block_ref.capacity);
+                    let new_block = last_block_ref.new_next(3, capacity).into_raw();
             
-                    // 3. Connect new block with old
-                    last_block.next.store(new_block.as_ptr(), Ordering::Release);
+                    // 3. Connect new block with old, and set it as last.
+                    last_block_ref.next.store(new_block.as_ptr(), Ordering::SeqCst); // SeqCst for Notifier.
+                    lock.block = new_block;
                     
                     // 4. Arc -- old block
                     unsafe{
-                        Block::dec_use_count(last_block.into());
+                        Block::dec_use_count(last_block);
                     }
                     
                     unsafe{ BlockArc::from_raw(new_block) }                    
@@ -147,24 +418,246 @@ impl<T> Queue<T> {
                 
                 let result = new_block.try_push(value);
                 if result.is_err(){
-                    unsafe{ std::hint::unreachable_unchecked() }
+                    unsafe{ core::hint::unreachable_unchecked() }
                 }
                 
-                // 5. Set new block as last, and release lock.
-                this.unlock_last_block(new_block.as_non_null());
+                // 5. Release lock.
+                drop(lock);
+                
+                this.block_inserted(&new_block);
             }
-            insert_block_and_push(self, unsafe{block.as_ref()}, value);
-            return;
+            insert_block_and_push(self, lock, value);
+        } else {
+            drop(lock);
         }
-        self.unlock_last_block(block);
+        self.subscriptions.message_notifier.notify();
     }
     
     #[must_use]
     #[inline]
-    pub fn writer(self: &Arc<Self>) -> Writer<T> {
+    pub fn writer(self: &Arc<Self>) -> Writer<T, N> {
+        self.subscriptions.add_writer();
         Writer {
             block: self.load_last_block(),
             event_queue: self.clone(),
+            room: if self.bound.is_some() { 0 } else { usize::MAX },
+        }
+    }
+    
+    /// Returns latch, that will be released after `n` more readers
+    /// are constructed with [reader()].
+    ///
+    /// [reader()]: Self::reader
+    #[must_use]
+    #[inline]
+    pub fn barrier(&self, n: usize) -> ReaderLatch {
+        ReaderLatch::new(self.subscriptions.clone(), n)
+    }
+
+    /// Blocks until every reader consumes all messages pushed before this call.
+    /// 
+    /// Pushes in progress, that already reserved their slot, are waited for as well.
+    ///
+    /// Readers report their progress when they run out of messages - that is,
+    /// when [next()] returns `None`. So this will block forever, if some reader
+    /// is not polled.
+    ///
+    /// Readers dropped during the wait are not waited for. Readers constructed
+    /// during the wait start past the messages in question, so they do not
+    /// delay it.
+    ///
+    /// [next()]: LendingReader::next
+    pub fn quiesce(&self) {
+        self.subscriptions.wait_readers(self.write_position());
+    }
+    
+    /// Release memory fence. See [crate#memory-ordering].
+    #[inline]
+    pub fn release_fence(&self) {
+        fence(Ordering::Release);
+    }
+    
+    /// Is queue still in its first block? 
+    /// 
+    /// `false` means queue has overflowed its first block at least once.
+    #[inline]
+    pub fn is_single_block(&self) -> bool {
+        self.load_last_block().seq == 0
+    }
+    
+    /// Sets `f` to be called once, when the queue overflows its first block -
+    /// that is, when the second block is allocated. 
+    /// 
+    /// A canary for queues, expected to stay small. Never called, if queue
+    /// has already overflowed. Replaces previously set callback.
+    /// 
+    /// Called from the writer, that allocated the block - outside of 
+    /// queue locks.
+    #[inline]
+    pub fn on_first_overflow(&self, f: impl FnOnce() + Send + 'static) {
+        *self.on_first_overflow.lock() = Some(Box::new(f));
+    }
+    
+    /// Calls [on_first_overflow()] callback, if `block` is the second one.
+    /// 
+    /// [on_first_overflow()]: Self::on_first_overflow
+    #[inline]
+    fn block_inserted(&self, block: &Block<T>) {
+        if block.seq == 1 {
+            let f = self.on_first_overflow.lock().take();
+            if let Some(f) = f {
+                f();
+            }
+        }
+    }
+    
+    /// Writer contention counters.
+    #[cfg(feature = "metrics")]
+    #[inline]
+    pub fn metrics(&self) -> crate::Metrics {
+        self.counters.snapshot()
+    }
+    
+    /// Position of the next pushed message. Includes pushes in progress.
+    #[inline]
+    fn write_position(&self) -> usize {
+        let last_block = self.load_last_block();
+        let block_len  = last_block.len.load(Ordering::Acquire).min(last_block.capacity);
+        last_block.start + block_len
+    }
+    
+    /// Number of messages, that can be pushed at `position`, within bound.
+    #[inline]
+    fn room(&self, position: usize) -> usize {
+        match self.bound {
+            Some(bound) => self.subscriptions.min_position()
+                .saturating_add(bound)
+                .saturating_sub(position),
+            None => usize::MAX,
+        }
+    }
+    
+    /// Blocks until there is room for `n` messages at `position`.
+    /// Returns room.
+    #[cold]
+    #[inline(never)]
+    fn wait_room(&self, position: usize, n: usize) -> usize {
+        loop {
+            let room = self.room(position);
+            if room >= n {
+                return room;
+            }
+            if let Some(bound) = self.bound {
+                self.subscriptions.wait_readers(position + n - bound);
+            }
+        }
+    }
+
+    /// Same as [reader()], but wrapped in [Subscription] - 
+    /// counted in [subscriber_count()] until dropped.
+    /// 
+    /// [reader()]: Self::reader
+    /// [subscriber_count()]: Self::subscriber_count
+    #[must_use]
+    #[inline]
+    pub fn subscribe(&self) -> Subscription<Reader<T>> {
+        let reader = self.reader();
+        let id = self.subscriptions.add_subscriber(&reader.state);
+        Subscription::new(reader, id, self.subscriptions.clone())
+    }
+    
+    /// Marks queue closed - no more messages expected - and wakes 
+    /// blocked readers.
+    /// 
+    /// Does not prevent pushes. But messages pushed after close, may be 
+    /// missed by [Reader::recv_all_until_closed()] and [Reader::try_next()].
+    /// 
+    /// Dropped queue is closed as well.
+    #[inline]
+    pub fn close(&self) {
+        self.subscriptions.close();
+    }
+    
+    #[inline]
+    pub fn is_closed(&self) -> bool {
+        self.subscriptions.is_closed()
+    }
+    
+    /// Number of live [Subscription]s.
+    #[inline]
+    pub fn subscriber_count(&self) -> usize {
+        self.subscriptions.subscriber_count()
+    }
+    
+    /// Number of live [Reader]s, including clones and readers 
+    /// inside [Subscription]s.
+    /// 
+    /// Snapshot, for diagnostics - may be outdated immediately.
+    #[inline]
+    pub fn reader_count(&self) -> usize {
+        self.subscriptions.reader_count()
+    }
+    
+    /// Number of live [Writer]s.
+    /// 
+    /// Snapshot, for diagnostics. When 0, and the queue is not pushed 
+    /// directly, the remaining backlog is final.
+    #[inline]
+    pub fn writer_count(&self) -> usize {
+        self.subscriptions.writer_count()
+    }
+    
+    /// Lag of each live [Subscription] - number of messages between its 
+    /// reader and the queue end. By [Subscription::id()], in ascending order.
+    /// 
+    /// Reader reports its position, when it runs out of messages, and on 
+    /// each block switch. So reader busy with a backlog, shows lag since its 
+    /// last report - up to a block more, than the actual one.
+    pub fn readers_lag(&self) -> Vec<(u64, usize)> {
+        let write_position = self.write_position();
+        let mut positions = self.subscriptions.subscriber_positions();
+        for (_, position) in &mut positions {
+            *position = write_position.saturating_sub(*position);
+        }
+        positions
+    }
+    
+    /// Estimate of messages, that the slowest reader has not read yet. 
+    /// 0 if there are no readers.
+    /// 
+    /// Reader reports its position, when it runs out of messages, and on 
+    /// each block switch. So this is an upper bound, stale by less than 
+    /// a block - reader busy with a backlog, counts from its last report.
+    /// 
+    /// Snapshot under concurrent writers. Counts messages, that are being 
+    /// written, as well.
+    #[inline]
+    pub fn approx_len(&self) -> usize {
+        self.write_position().saturating_sub(self.subscriptions.min_position())
+    }
+    
+    /// [approx_len()] is 0 - every reader reported, that it read 
+    /// everything pushed.
+    /// 
+    /// [approx_len()]: Self::approx_len
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.approx_len() == 0
+    }
+    
+    /// Same as [reader()], but reader can [rewind] to this point later.
+    /// 
+    /// [reader()]: Self::reader
+    /// [rewind]: RewindableReader::rewind_to_subscription
+    #[must_use]
+    #[inline]
+    pub fn reader_rewindable(&self) -> RewindableReader<Reader<T>> {
+        let origin = self.reader();
+        // Origin is not a consumer - it should not hold back quiesce().
+        origin.state.report(usize::MAX);
+        RewindableReader{
+            reader: origin.clone(),
+            origin,
         }
     }
     
@@ -173,22 +666,95 @@ impl<T> Queue<T> {
     #[inline]
     pub fn reader(&self) -> Reader<T> {
         let last_block = self.load_last_block();
-        let block_len  = last_block.len.load(Ordering::Acquire);
+        // Block len can temporarily exceed block capacity, while writer 
+        // tries to push to the full block.
+        let block_len  = last_block.len.load(Ordering::Acquire).min(last_block.capacity);
+        let state = self.subscriptions.subscribe(last_block.start + block_len);
+        let tombstones = last_block.has_tombstones();
         Reader {
             block: last_block,
             index: block_len,
             len:   block_len,
-            bitblock_index: block_len/64
+            bitblock_index: block_len/64,
+            tombstones,
+            state,
+            on_block_advance: None,
+            counters: Default::default(),
+        }
+    }
+}
+/// Future returned by [Queue::push_async()].
+#[must_use = "futures do nothing unless polled"]
+pub struct PushAsync<'a, T, const N: usize = BLOCK_SIZE> {
+    queue: &'a Queue<T, N>,
+    value: Option<T>,
+}
+
+// Value is moved into the queue as is, it is never pinned.
+impl<T, const N: usize> Unpin for PushAsync<'_, T, N> {}
+
+impl<T, const N: usize> Future for PushAsync<'_, T, N> {
+    type Output = ();
+
+    #[inline]
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+        let this = self.get_mut();
+        if let Some(lock) = this.queue.try_lock_last_block() {
+            if this.queue.bound.is_some() {
+                let block = unsafe{ lock.block.as_ref() };
+                let position = block.start + block.len.load(Ordering::Acquire).min(block.capacity);
+                if this.queue.room(position) == 0 {
+                    drop(lock);
+                    #[cfg(feature = "async")]
+                    {
+                        // Woken, when reader reports its position.
+                        this.queue.subscriptions.position_notifier.register(cx.waker());
+                        // Re-check after registration, in case report was before it.
+                        if this.queue.room(position) == 0 {
+                            return Poll::Pending;
+                        }
+                    }
+                    // Without `async` feature, readers can not wake us - poll again.
+                    cx.waker().wake_by_ref();
+                    return Poll::Pending;
+                }
+            }
+            let value = this.value.take().expect("PushAsync polled after completion.");
+            this.queue.push_and_unlock(lock, value);
+            Poll::Ready(())
+        } else {
+            // Lock is held for a short time - retry right away.
+            cx.waker().wake_by_ref();
+            Poll::Pending
         }
     }
 }
-impl<T> Drop for Queue<T> {
+
+/// Locked `Queue::last_block`. Unlocks on drop.
+/// 
+/// Unlocks on unwind too - so panic in critical section does not leave
+/// `last_block` null, and other writers do not spin on it forever.
+struct LastBlockLock<'a, T, const N: usize> {
+    queue: &'a Queue<T, N>,
+    /// Stored to `last_block` on unlock. Replace, once the new block is linked.
+    block: NonNull<Block<T>>,
+}
+
+impl<T, const N: usize> Drop for LastBlockLock<'_, T, N> {
+    #[inline]
+    fn drop(&mut self) {
+        self.queue.last_block.store(self.block.as_ptr(), Ordering::Release);
+    }
+}
+
+impl<T, const N: usize> Drop for Queue<T, N> {
     #[inline]
     fn drop(&mut self) {
         let last_block = self.last_block.load(Ordering::Acquire);
         unsafe{
             Block::dec_use_count(NonNull::new_unchecked(last_block));
         }
+        self.subscriptions.close();
     }
 }
 
@@ -199,19 +765,53 @@ impl<T> Drop for Queue<T> {
 /// Block pointer updated to the latest one on each [push()] or [update()].
 /// You also can just construct a new Writer for each write session.
 ///
+/// Writer is [Send] if `T` is [Send], so it can be constructed on one thread
+/// and moved to another:
+/// ```
+/// # use chute::mpmc::Queue;
+/// let queue = Queue::new();
+/// let mut writer = queue.writer();
+/// writer.push(1);
+/// std::thread::spawn(move || writer.push(2)).join().unwrap();
+/// ```
+///
+/// Writer of non-[Send] messages is not [Send]:
+/// ```compile_fail
+/// # use chute::mpmc::Queue;
+/// let queue = Queue::new();
+/// let mut writer = queue.writer();
+/// writer.push(std::rc::Rc::new(1));
+/// std::thread::spawn(move || writer.push(std::rc::Rc::new(2)));
+/// ```
+///
 /// Constructed by [Queue::writer()].
 ///
 /// [push()]: Self::push
 /// [update()]: Self::update
 /// [Queue::writer()]: crate::mpmc::Queue::writer
-pub struct Writer<T> {
+pub struct Writer<T, const N: usize = BLOCK_SIZE> {
     block: BlockArc<T>,
-    event_queue: Arc<Queue<T>>
+    pub(crate) event_queue: Arc<Queue<T, N>>,
+    /// Pushes left, before checking queue bound again.
+    room: usize,
 }
 
-unsafe impl<T> Send for Writer<T>{}
+// Writer only moves `T`s into the queue, it never shares them.
+unsafe impl<T: Send, const N: usize> Send for Writer<T, N>{}
+
+impl<T, const N: usize> Drop for Writer<T, N> {
+    #[inline]
+    fn drop(&mut self) {
+        self.event_queue.subscriptions.remove_writer();
+    }
+}
 
-impl<T> Writer<T> {
+impl<T, const N: usize> Writer<T, N> {
+    /// Max blocks [update()] walks, before loading the last block from the queue.
+    /// 
+    /// [update()]: Self::update
+    pub const UPDATE_MAX_JUMPS: usize = 5;
+    
     #[inline]
     fn fast_forward_to_last_block(&mut self, max_jumps: usize) -> Result<(), ()> {
         let mut last = self.block.as_non_null();
@@ -233,12 +833,24 @@ impl<T> Writer<T> {
         Err(())
     }
     
-    /// UNTESTED
-    /// 
     /// Moves writer's internal block pointer to the latest in a queue.
     /// This prevents writer from keeping a potentially unused blocks alive. 
+    /// 
+    /// Writer holds the block of its last push - and, since blocks are linked,
+    /// all the blocks after it. Push itself always lands in the last block, 
+    /// so this matters only for writers, that stay idle while others push. 
+    /// Call before going idle for long.
+    /// 
+    /// Walks up to [UPDATE_MAX_JUMPS] blocks. If writer is further behind - 
+    /// loads the last block from the queue, which contends with writers 
+    /// inserting blocks. Such fallbacks are counted in 
+    /// [Metrics::writer_update_fallbacks].
+    /// 
+    /// [UPDATE_MAX_JUMPS]: Self::UPDATE_MAX_JUMPS
+    /// [Metrics::writer_update_fallbacks]: crate::Metrics::writer_update_fallbacks
     pub fn update(&mut self) {
-        if self.fast_forward_to_last_block(5).is_err() {
+        if self.fast_forward_to_last_block(Self::UPDATE_MAX_JUMPS).is_err() {
+            self.event_queue.counters.writer_update_fallback();
             self.block = self.event_queue.load_last_block();
         }
     }
@@ -262,11 +874,22 @@ impl<T> Writer<T> {
     }*/
     
     
+    /// For bounded queue - blocks until `n` messages fit in bound.
+    #[inline]
+    fn reserve_room(&mut self, n: usize) {
+        if unlikely(self.room < n) {
+            let block_len = self.block.len.load(Ordering::Acquire).min(self.block.capacity);
+            self.room = self.event_queue.wait_room(self.block.start + block_len, n);
+        }
+        self.room -= n;
+    }
+    
     #[cold]
     #[inline(never)]
     fn insert_block_and_push(&mut self, mut value: T){
         // TODO: try load next first? 
         loop{
+            self.event_queue.counters.full_block_push();
             (self.block, _) = self.event_queue.insert_block();
             
             let inserted = self.block.try_push(value);
@@ -278,137 +901,2529 @@ impl<T> Writer<T> {
         }
     }    
 
+    /// Push value, and wake readers blocked in [Reader::wait()].
+    /// 
+    /// Same as [push()] followed by [notify()] - pays a full memory fence.
+    /// 
+    /// [push()]: Self::push
+    /// [notify()]: Self::notify
+    #[inline]
+    pub fn push_notify(&mut self, value: T) {
+        self.push(value);
+        self.notify();
+    }
+    
+    /// Push value. Does not wake readers blocked in [Reader::wait()].
+    /// 
+    /// Readers that are not blocked see the value as usual. 
+    /// Use [push_notify()], or call [notify()] after a batch of pushes, 
+    /// to wake blocked readers.
+    /// 
+    /// [push_notify()]: Self::push_notify
+    /// [notify()]: Self::notify
     #[inline]
     pub fn push(&mut self, value: T) {
+        self.reserve_room(1);
         let inserted = self.block.try_push(value);
         if let Err(value) = inserted {
             self.insert_block_and_push(value);
         }
     }
-}
-
-/// Queue consumer.
-/// 
-/// Constructed by [Queue::reader()].
-pub struct Reader<T>{
-    pub(crate) block: BlockArc<T>,
-    pub(crate) index: usize,
-    pub(crate) len  : usize,
-    pub(crate) bitblock_index  : usize,
-}
-
-impl<T> Clone for Reader<T> {
-    #[inline]
-    fn clone(&self) -> Self {
-        Self{
-            block: self.block.clone(),
-            index: self.index,
-            len  : self.len,
-            bitblock_index: self.bitblock_index
+    
+    /// Push all `values` at once, and wake blocked readers. 
+    /// `values` is left empty.
+    /// 
+    /// Values land contiguously, and readers see either none or all of them.
+    /// Values of other writers never interleave with the group.
+    /// 
+    /// Takes `Vec`, not iterator - values are moved with one copy, and
+    /// there is no user code, that could panic, between slot reservation and
+    /// publish. For iterators, see [push_iter()].
+    /// 
+    /// # Panics
+    /// 
+    /// Panics if there are more than block size values, or more than 
+    /// [bound] values - such group would never fit.
+    /// 
+    /// [push_iter()]: Self::push_iter
+    /// [bound]: Queue::bounded
+    pub fn push_group(&mut self, values: &mut Vec<T>) {
+        let n = values.len();
+        if n == 0 {
+            return;
         }
-    }
-}
-
-impl<T> LendingReader for Reader<T> {
+        assert!(n <= N, "Group does not fit in block.");
+        assert!(
+            n <= self.event_queue.bound.unwrap_or(usize::MAX), 
+            "Group does not fit in queue bound."
+        );
+        self.reserve_room(n);
+        
+        if let Some(index) = self.block.try_reserve(n) {
+            unsafe{
+                self.block.write(index, values.as_ptr(), n);
+                values.set_len(0);
+                self.block.publish(index, n);
+            }
+        } else {
+            self.event_queue.counters.full_block_push();
+            let (block, index, next_block) = self.event_queue.reserve_group(n);
+            let len = cmp::min(n, block.capacity.saturating_sub(index));
+            unsafe{
+                // `index` can be past the block end - when group starts 
+                // in the next block.
+                if len != 0 {
+                    block.write(index, values.as_ptr(), len);
+                }
+                if let Some(next_block) = &next_block {
+                    next_block.write(0, values.as_ptr().add(len), n - len);
+                }
+                values.set_len(0);
+                
+                // Publish the tail first - readers can reach it only
+                // through the head.
+                if let Some(next_block) = &next_block {
+                    next_block.publish(0, n - len);
+                }
+                if len != 0 {
+                    block.publish(index, len);
+                }
+            }
+            self.block = next_block.unwrap_or(block);
+        }
+        self.notify();
+    }
+    
+    /// Push all values of `iter`, and wake blocked readers.
+    /// 
+    /// Values are pushed with [push_group()], by block size chunks - 
+    /// one slot reservation per chunk, instead of one per value. 
+    /// Chunk is visible to readers as a whole, but values of other writers 
+    /// may land between chunks.
+    /// 
+    /// ```
+    /// # use chute::LendingReader;
+    /// let queue = chute::mpmc::Queue::new();
+    /// let mut reader = queue.reader();
+    /// let mut writer = queue.writer();
+    /// writer.push_iter(0..3);
+    /// assert_eq!(reader.next_chunk(), Some(&[0, 1, 2][..]));
+    /// ```
+    /// 
+    /// [push_group()]: Self::push_group
+    pub fn push_iter<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let mut iter = iter.into_iter();
+        // Group should fit in both block and bound.
+        let chunk_len = cmp::min(N, self.event_queue.bound.unwrap_or(usize::MAX));
+        let mut values = Vec::with_capacity(cmp::min(iter.size_hint().0, chunk_len));
+        loop {
+            // Iterator is drained before reserving - so that its panic
+            // does not leave reserved slots behind.
+            values.extend(iter.by_ref().take(chunk_len));
+            if values.is_empty() {
+                break;
+            }
+            self.push_group(&mut values);
+        }
+    }
+    
+    /// Reserves a slot at the queue end, to [write()] the value later.
+    /// 
+    /// Dropping the guard without write aborts the push - the slot is published
+    /// as tombstone, that readers skip.
+    /// 
+    /// Until the slot is written or aborted, readers do not see messages 
+    /// of other writers, pushed after it. So do not hold the guard for long.
+    /// 
+    /// ```
+    /// # use chute::LendingReader;
+    /// let queue = chute::mpmc::Queue::new();
+    /// let mut reader = queue.reader();
+    /// let mut writer = queue.writer();
+    /// 
+    /// writer.begin_push().write(1);
+    /// drop(writer.begin_push());
+    /// writer.begin_push().write(3);
+    /// 
+    /// assert_eq!(reader.next(), Some(&1));
+    /// assert_eq!(reader.next(), Some(&3));
+    /// assert_eq!(reader.next(), None);
+    /// ```
+    /// 
+    /// [write()]: SlotGuard::write
+    pub fn begin_push(&mut self) -> SlotGuard<'_, T, N> {
+        self.reserve_room(1);
+        let index = match self.block.try_reserve(1) {
+            Some(index) => index,
+            None => {
+                self.event_queue.counters.full_block_push();
+                let (block, index, next_block) = self.event_queue.reserve_group(1);
+                match next_block {
+                    Some(next_block) => {
+                        self.block = next_block;
+                        0
+                    }
+                    None => {
+                        self.block = block;
+                        index
+                    }
+                }
+            }
+        };
+        SlotGuard{ writer: self, index }
+    }
+    
+    /// Push value, constructed by `init` right in the queue memory. 
+    /// Does not wake blocked readers - same as [push()].
+    /// 
+    /// Large `T` is not moved through the stack this way.
+    /// 
+    /// ```
+    /// let queue = chute::mpmc::Queue::<[u8; 1024]>::new();
+    /// let mut writer = queue.writer();
+    /// unsafe{
+    ///     writer.push_in_place(|ptr| ptr.cast::<u8>().write_bytes(1, 1024));
+    /// }
+    /// ```
+    /// 
+    /// # Safety
+    /// 
+    /// `init` must fully initialize the value at pointer. If `init` panics,
+    /// push is aborted as with [begin_push()] - slot is skipped by readers,
+    /// and nothing is dropped in it.
+    /// 
+    /// [push()]: Self::push
+    /// [begin_push()]: Self::begin_push
+    #[inline]
+    pub unsafe fn push_in_place(&mut self, init: impl FnOnce(*mut T)) {
+        let slot = self.begin_push();
+        init(slot.as_mut_ptr());
+        slot.commit();
+    }
+    
+    /// Wake readers blocked in [Reader::wait()].
+    /// 
+    /// Cheap if there are no blocked readers - but still a full memory fence.
+    #[inline]
+    pub fn notify(&mut self) {
+        self.event_queue.subscriptions.message_notifier.notify();
+    }
+}
+
+/// Reserved queue slot. 
+/// 
+/// Either [write()] the value, or drop to abort.
+/// 
+/// Constructed by [Writer::begin_push()].
+/// 
+/// [write()]: Self::write
+#[must_use = "dropping guard aborts the push"]
+pub struct SlotGuard<'a, T, const N: usize = BLOCK_SIZE> {
+    writer: &'a mut Writer<T, N>,
+    /// Slot index in writer's block.
+    index: usize,
+}
+
+impl<T, const N: usize> SlotGuard<'_, T, N> {
+    /// Writes `value` to the slot. Does not wake blocked readers - same as
+    /// [Writer::push()].
+    #[inline]
+    pub fn write(self, value: T) {
+        unsafe{
+            self.as_mut_ptr().write(value);
+            self.commit();
+        }
+    }
+    
+    #[inline]
+    fn as_mut_ptr(&self) -> *mut T {
+        unsafe{ self.writer.block.mem().add(self.index) }
+    }
+    
+    /// Publish the slot.
+    /// 
+    /// Slot must be initialized.
+    #[inline]
+    unsafe fn commit(self) {
+        let this = ManuallyDrop::new(self);
+        this.writer.block.publish(this.index, 1);
+    }
+}
+
+impl<T, const N: usize> Drop for SlotGuard<'_, T, N> {
+    #[inline]
+    fn drop(&mut self) {
+        unsafe{ self.writer.block.abort(self.index); }
+    }
+}
+
+/// Buffered queue producer.
+///
+/// Stages pushed values in a local buffer, and publishes them to the queue
+/// on [flush()]. Staged values are owned by `BufWriter`, readers do not see them
+/// until flushed.
+///
+/// On drop, pending values are either published (default) or dropped in place,
+/// depending on [flush_on_drop()]. This applies to any drop - including early
+/// returns and panic unwinding. So with `flush_on_drop(false)`, a partially built
+/// batch is discarded on error paths, unless explicitly flushed.
+///
+/// [flush()]: Self::flush
+/// [flush_on_drop()]: Self::flush_on_drop
+pub struct BufWriter<T, const N: usize = BLOCK_SIZE> {
+    writer: Writer<T, N>,
+    buffer: Vec<T>,
+    flush_on_drop: bool,
+}
+
+impl<T, const N: usize> BufWriter<T, N> {
+    #[must_use]
+    #[inline]
+    pub fn new(queue: &Arc<Queue<T, N>>) -> Self {
+        Self::from_writer(queue.writer())
+    }
+    
+    #[must_use]
+    #[inline]
+    pub fn from_writer(writer: Writer<T, N>) -> Self {
+        Self{
+            writer,
+            buffer: Vec::new(),
+            flush_on_drop: true,
+        }
+    }
+    
+    /// Should pending values be published on drop? `true` by default.
+    /// 
+    /// If `false` - pending values are dropped instead.
+    #[must_use]
+    #[inline]
+    pub fn flush_on_drop(mut self, flush_on_drop: bool) -> Self {
+        self.flush_on_drop = flush_on_drop;
+        self
+    }
+    
+    /// Stage value. It will not be visible to readers until [flush()].
+    /// 
+    /// [flush()]: Self::flush
+    #[inline]
+    pub fn push(&mut self, value: T) {
+        self.buffer.push(value);
+    }
+    
+    /// Number of staged values.
+    #[inline]
+    pub fn pending(&self) -> usize {
+        self.buffer.len()
+    }
+    
+    /// Publish all staged values to the queue, in push order.
+    #[inline]
+    pub fn flush(&mut self) {
+        for value in self.buffer.drain(..) {
+            self.writer.push(value);
+        }
+        self.writer.notify();
+    }
+    
+    /// Drop all staged values, without publishing them.
+    #[inline]
+    pub fn discard(&mut self) {
+        self.buffer.clear();
+    }
+}
+
+impl<T, const N: usize> Drop for BufWriter<T, N> {
+    #[inline]
+    fn drop(&mut self) {
+        if self.flush_on_drop {
+            self.flush();
+        }
+        // otherwise - buffer drops the pending values.
+    }
+}
+
+/// Queue consumer.
+/// 
+/// Constructed by [Queue::reader()].
+pub struct Reader<T>{
+    pub(crate) block: BlockArc<T>,
+    pub(crate) index: usize,
+    pub(crate) len  : usize,
+    /// Bitblock scan start. 
+    /// 
+    /// All bitblocks before it are known to be full, so `len` re-read starts
+    /// here. Advanced as the reader progresses, and reset to 0 only 
+    /// on block switch. Readers constructed mid-block start from their
+    /// position's bitblock.
+    pub(crate) bitblock_index  : usize,
+    /// Does current block have aborted slots? 
+    /// 
+    /// Refreshed on each `len` re-read.
+    pub(crate) tombstones: bool,
+    pub(crate) state: ReaderState,
+    /// Called with block sequence number on each block switch.
+    pub(crate) on_block_advance: Option<Box<dyn FnMut(u64) + Send + Sync>>,
+    counters: ReaderCounters,
+}
+
+impl<T> Reader<T> {
+    /// Position in queue.
+    #[inline]
+    pub(crate) fn position(&self) -> usize {
+        self.block.start + self.index
+    }
+    
+    /// Acquire memory fence. See [crate#memory-ordering].
+    #[inline]
+    pub fn acquire_fence(&self) {
+        fence(Ordering::Acquire);
+    }
+    
+    /// Reader position - see [ReaderOffset].
+    #[inline]
+    pub fn offset(&self) -> ReaderOffset {
+        ReaderOffset(self.position() as u64)
+    }
+    
+    /// Moves reader forward to `offset`, skipping messages in between.
+    /// 
+    /// Fails with [Error::OffsetUnavailable], if `offset` is behind reader - 
+    /// blocks there may be already released. Or if it is past published 
+    /// messages. Reader is not moved then.
+    pub fn seek(&mut self, offset: ReaderOffset) -> Result<(), Error> {
+        let position = usize::try_from(offset.0).map_err(|_| Error::OffsetUnavailable)?;
+        if position < self.position() || position > self.published_position() {
+            return Err(Error::OffsetUnavailable);
+        }
+        self.skip_to(position);
+        Ok(())
+    }
+    
+    /// Clones messages in `[start, end)` offset range, without moving reader.
+    /// 
+    /// Only published messages are cloned - messages being written past
+    /// `end` are never touched. Fails with [Error::OffsetUnavailable], if
+    /// `start` is behind reader - blocks there may be already released.
+    /// Or if `end` is past published messages.
+    /// Aborted messages are skipped.
+    pub fn snapshot_range(&self, start: ReaderOffset, end: ReaderOffset) 
+        -> Result<Vec<T>, Error>
+    where
+        T: Clone
+    {
+        let start = usize::try_from(start.0).map_err(|_| Error::OffsetUnavailable)?;
+        let end   = usize::try_from(end.0).map_err(|_| Error::OffsetUnavailable)?;
+        if start < self.position() || end < start || end > self.published_position() {
+            return Err(Error::OffsetUnavailable);
+        }
+        
+        let mut values = Vec::with_capacity(end - start);
+        let mut block = self.block.clone();
+        let mut position = start;
+        while position < end {
+            while position >= block.start + block.capacity {
+                // Exists - everything before `end` is published.
+                block = block.try_load_next(Ordering::Acquire).unwrap();
+            }
+            let block_end = cmp::min(end, block.start + block.capacity);
+            for index in position - block.start..block_end - block.start {
+                if block.is_tombstone(index) {
+                    continue;
+                }
+                values.push(unsafe{ &*block.mem().add(index) }.clone());
+            }
+            position = block_end;
+        }
+        Ok(values)
+    }
+    
+    /// Position after the last message, before which all are published.
+    fn published_position(&self) -> usize {
+        let mut block = self.block.clone();
+        let mut bitblock_index = self.bitblock_index;
+        loop {
+            let capacity = block.capacity;
+            let mut len = bitblock_index * 64;
+            while len < capacity {
+                let bit_block = unsafe {
+                    block.bit_blocks().get_unchecked(bitblock_index)
+                }.load(Ordering::Acquire);
+                len = bitblock_index*64 + bit_block.trailing_ones() as usize;
+                if bit_block != u64::MAX {
+                    break;
+                }
+                bitblock_index += 1;
+            }
+            if len < capacity {
+                return block.start + len;
+            }
+            match block.try_load_next(Ordering::Acquire) {
+                Some(next_block) => {
+                    block = next_block;
+                    bitblock_index = 0;
+                }
+                None => return block.start + capacity,
+            }
+        }
+    }
+    
+    #[inline]
+    fn report_position(&self) {
+        self.state.report(self.position());
+    }
+    
+    /// Sets `f` to be called each time reader moves to the next block,
+    /// with that block's sequence number (first block is 0).
+    /// 
+    /// Called from within [next()], so keep it cheap. Replaces previously
+    /// set callback. Not inherited by clones.
+    /// 
+    /// [next()]: LendingReader::next
+    #[inline]
+    pub fn on_block_advance(&mut self, f: impl FnMut(u64) + Send + Sync + 'static) {
+        self.on_block_advance = Some(Box::new(f));
+    }
+    
+    /// Read overhead counters. Not inherited by clones.
+    #[cfg(feature = "metrics")]
+    #[inline]
+    pub fn diagnostics(&self) -> crate::ReaderDiagnostics {
+        self.counters.snapshot()
+    }
+    
+    /// Moves reader forward to `other`'s position, if `other` is ahead.
+    /// Messages in between are skipped. If `other` is behind - does nothing.
+    /// 
+    /// # Panics
+    /// 
+    /// Panics if readers belong to different queues.
+    /// See [try_advance_to()] for non-panicking version.
+    /// 
+    /// [try_advance_to()]: Self::try_advance_to
+    #[inline]
+    pub fn advance_to(&mut self, other: &Reader<T>) {
+        if let Err(error) = self.try_advance_to(other) {
+            panic!("{error}");
+        }
+    }
+    
+    /// Same as [advance_to()], but returns [Error::DifferentQueues] 
+    /// instead of panic.
+    /// 
+    /// [advance_to()]: Self::advance_to
+    pub fn try_advance_to(&mut self, other: &Reader<T>) -> Result<(), Error> {
+        if other.position() > self.position() {
+            if !self.block.is_followed_by(&other.block) {
+                return Err(Error::DifferentQueues);
+            }
+            self.block = other.block.clone();
+            self.index = other.index;
+            self.len   = other.len;
+            self.bitblock_index = other.bitblock_index;
+            self.tombstones = other.tombstones;
+        } else if !other.block.is_followed_by(&self.block) {
+            return Err(Error::DifferentQueues);
+        }
+        Ok(())
+    }
+    
+    /// Same as [next()], but returns pinned reference.
+    /// 
+    /// Messages never move after being pushed, see [pinning].
+    /// 
+    /// [next()]: LendingReader::next
+    /// [pinning]: crate#pinning
+    #[inline]
+    pub fn next_pinned(&mut self) -> Option<Pin<&T>> {
+        // Values stay in place until dropped in place with their block.
+        self.next().map(|value| unsafe{ Pin::new_unchecked(value) })
+    }
+    
+    /// Calls `f` for each block this reader keeps alive - from its current 
+    /// block, to the last block of the queue.
+    /// 
+    /// Blocks before the reader's one, are not visible to it. To inspect the
+    /// whole queue, use the reader that is furthest behind.
+    /// 
+    /// This is read-only and best-effort - queue may change during the walk.
+    #[inline]
+    pub fn for_each_block(&self, f: impl FnMut(&BlockInfo)) {
+        self.block.for_each(f)
+    }
+    
+    /// Number of messages pushed to `queue`, that reader did not consume yet.
+    /// Includes pushes in progress.
+    /// 
+    /// `queue` must be the queue reader belongs to.
+    /// 
+    /// This is best-effort - concurrent writers can change it at any moment.
+    #[inline]
+    pub fn catch_up_count<const N: usize>(&self, queue: &Queue<T, N>) -> usize {
+        queue.write_position().saturating_sub(self.position())
+    }
+    
+    /// Number of published messages, that reader did not consume yet.
+    /// Same as [catch_up_count()], but without the queue.
+    /// 
+    /// Walks blocks from reader to the queue end. Snapshot - writers may 
+    /// push during the walk. 0 at the queue end.
+    /// 
+    /// [catch_up_count()]: Self::catch_up_count
+    #[inline]
+    pub fn lag(&self) -> usize {
+        self.published_position().saturating_sub(self.position())
+    }
+    
+    /// Drops the backlog - moves reader to the queue end, as if it was
+    /// just constructed by [Queue::reader()]. Reads only messages pushed 
+    /// from now on.
+    /// 
+    /// Messages between old position and the queue end are permanently 
+    /// skipped for this reader.
+    /// 
+    /// Walks blocks from reader to the queue end - as [lag()].
+    /// 
+    /// [lag()]: Self::lag
+    pub fn skip_to_tail(&mut self) {
+        self.skip_to(self.published_position());
+        self.report_position();
+    }
+    
+    /// Queue is closed, and reader consumed everything pushed before close.
+    /// Reader will never return a message again.
+    /// 
+    /// Walks blocks from reader to the queue end - as [lag()].
+    /// 
+    /// [lag()]: Self::lag
+    pub fn is_closed_and_drained(&self) -> bool {
+        // Close is SeqCst - everything pushed before it is visible now.
+        self.state.subscriptions().is_closed() && self.lag() == 0
+    }
+    
+    /// Is there something new to read?
+    /// 
+    /// SeqCst, for Notifier.
+    #[cfg(any(feature = "std", feature = "async"))]
+    fn has_news(&self) -> bool {
+        if self.index != self.len {
+            return true;
+        }
+        if self.len == self.block.capacity {
+            return !self.block.next.load(Ordering::SeqCst).is_null();
+        }
+        let bit_block = unsafe {
+            self.block.bit_blocks().get_unchecked(self.bitblock_index)
+        }.load(Ordering::SeqCst);
+        let new_len = self.bitblock_index*64 + bit_block.trailing_ones() as usize;
+        new_len != self.len
+    }
+    
+    /// Blocks until there is something new to read.
+    /// 
+    /// Thread is parked, instead of spinning. Woken up by [Writer::push_notify()],
+    /// [Writer::notify()], group pushes and [Queue::blocking_push()]. Messages 
+    /// pushed with [Writer::push()] do not wake reader, until [Writer::notify()].
+    /// 
+    /// Returns immediately, if there is something to read already, or
+    /// queue is [closed]. After return, [next()] may still return `None`, 
+    /// if the only news was a new block. 
+    /// 
+    /// [next()]: LendingReader::next
+    /// [closed]: Queue::close
+    #[cfg(feature = "std")]
+    #[inline]
+    pub fn wait(&self) {
+        let subscriptions = self.state.subscriptions();
+        if self.has_news() || subscriptions.is_closed() {
+            return;
+        }
+        subscriptions.message_notifier.wait_while(|| 
+            !self.has_news() && !subscriptions.is_closed()
+        );
+    }
+    
+    /// Clones every message, blocking for more with [wait()], until 
+    /// queue is [closed] and everything pushed before close is read.
+    /// 
+    /// Without [close()] - blocks forever.
+    /// 
+    /// ```
+    /// let queue = chute::mpmc::Queue::new();
+    /// let mut reader = queue.reader();
+    /// let mut writer = queue.writer();
+    /// let producer = std::thread::spawn(move || {
+    ///     for i in 0..10 {
+    ///         writer.push(i);
+    ///     }
+    ///     queue.close();
+    /// });
+    /// assert!(reader.recv_all_until_closed().eq(0..10));
+    /// # producer.join().unwrap();
+    /// ```
+    /// 
+    /// [wait()]: Self::wait
+    /// [closed]: Queue::close
+    /// [close()]: Queue::close
+    #[cfg(feature = "std")]
+    pub fn recv_all_until_closed(&mut self) -> impl Iterator<Item = T> + '_
+    where
+        T: Clone
+    {
+        core::iter::from_fn(move || loop {
+            // Check before read - to not miss messages pushed before close.
+            let closed = self.state.subscriptions().is_closed();
+            if let Some(value) = self.next() {
+                return Some(value.clone());
+            }
+            if closed {
+                return None;
+            }
+            self.wait();
+        })
+    }
+    
+    /// Same as [next()], but tells apart empty queue - `Ok(None)`, and 
+    /// closed one - [Error::Closed].
+    /// 
+    /// Queue is closed, when it is dropped, or [closed] explicitly. [Error::Closed] is returned
+    /// after everything pushed before that is read. 
+    /// Costs nothing extra, while there are messages.
+    /// 
+    /// [next()]: LendingReader::next
+    /// [closed]: Queue::close
+    #[inline]
+    pub fn try_next(&mut self) -> Result<Option<&T>, Error> {
+        if let Some(value) = self.next() {
+            // NLL can not return conditional borrow, and then borrow again.
+            let value: *const T = value;
+            return Ok(Some(unsafe{ &*value }));
+        }
+        if !self.state.subscriptions().is_closed() {
+            return Ok(None);
+        }
+        // Everything pushed before close is visible now.
+        self.next().map(Some).ok_or(Error::Closed)
+    }
+    
+    /// Blocks until the next message, and returns it. `None` - if queue 
+    /// is closed, and everything is read. See [try_next()].
+    /// 
+    /// Does not block, if there is a message already. Otherwise parks
+    /// with [wait()] - so only wakes on [Writer::push_notify()], 
+    /// [Writer::notify()] and the rest listed there.
+    /// 
+    /// [try_next()]: Self::try_next
+    /// [wait()]: Self::wait
+    #[cfg(feature = "std")]
+    #[inline]
+    pub fn recv(&mut self) -> Option<&T> {
+        loop {
+            match self.try_next().map(|value| value.map(|value| value as *const T)) {
+                // NLL can not return conditional borrow, and then borrow again.
+                Ok(Some(value)) => return Some(unsafe{ &*value }),
+                Ok(None) => self.wait(),
+                Err(_) => return None,
+            }
+        }
+    }
+    
+    /// Converts to async [Stream] of cloned messages. See [ReaderStream].
+    /// 
+    /// [Stream]: futures_core::Stream
+    /// [ReaderStream]: crate::ReaderStream
+    #[cfg(feature = "async")]
+    #[inline]
+    pub fn into_stream(self) -> crate::ReaderStream<Self> {
+        crate::ReaderStream{ reader: self }
+    }
+    
+    /// Pointer to, and length of, the ready messages in the current block,
+    /// starting from reader's position. Reader is not advanced.
+    /// 
+    /// Messages in a block are contiguous, so this can be passed as is to 
+    /// FFI code, without copying. Ready region never spans several blocks -
+    /// it can be shorter than [catch_up_count()]. It also ends before
+    /// the first aborted slot (see [Writer::begin_push()]) - [next()] steps over it.
+    /// 
+    /// Getting the pointer is safe - dereferencing it is not. Pointer is 
+    /// valid for reads of `len` messages only while the reader stays in 
+    /// the current block - that is, until the next mutating call on 
+    /// the reader ([next()], [advance_to()], drop, etc.). Messages must not
+    /// be mutated or moved out through it.
+    /// 
+    /// [next()]: LendingReader::next
+    /// [advance_to()]: Self::advance_to
+    /// [catch_up_count()]: Self::catch_up_count
+    pub fn current_block_slice(&self) -> (*const T, usize) {
+        let capacity = self.block.capacity;
+        let mut len = self.len;
+        let mut bitblock_index = self.bitblock_index;
+        while len < capacity {
+            let bit_block = unsafe {
+                self.block.bit_blocks().get_unchecked(bitblock_index)
+            }.load(Ordering::Acquire);
+            len = bitblock_index*64 + bit_block.trailing_ones() as usize;
+            if bit_block != u64::MAX {
+                break;
+            }
+            bitblock_index += 1;
+        }
+        if self.block.has_tombstones() {
+            // Aborted slots are not initialized.
+            len = (self.index..len)
+                .find(|&index| self.block.is_tombstone(index))
+                .unwrap_or(len);
+        }
+        let ptr = unsafe{ self.block.mem().add(self.index) };
+        (ptr, len - self.index)
+    }
+    
+    /// Ready messages in the current block, from reader's position. 
+    /// Reader advances past them.
+    /// 
+    /// Never crosses block boundary - at the block end, iterator is empty, 
+    /// and [next()] moves to the next block. Ends where [current_block_slice()]
+    /// does.
+    /// 
+    /// [next()]: LendingReader::next
+    /// [current_block_slice()]: Self::current_block_slice
+    pub fn block_remaining_iter(&mut self) -> impl ExactSizeIterator<Item = &T> {
+        let (ptr, len) = self.current_block_slice();
+        let index = self.index + len;
+        self.index = index;
+        if index > self.len {
+            self.len = index;
+            self.bitblock_index = index / 64;
+        }
+        unsafe{ slice::from_raw_parts(ptr, len) }.iter()
+    }
+    
+    /// Returns the whole next block, if reader is at block boundary, and 
+    /// the block is full. Reader advances past it.
+    /// 
+    /// Returns `None` if block is not full yet, or reader is in the middle 
+    /// of block, or block has aborted slots - use [next()] to reach 
+    /// the boundary then.
+    /// 
+    /// [next()]: LendingReader::next
+    pub fn next_block(&mut self) -> Option<&[T]> {
+        if self.index == self.block.capacity {
+            let next_block = self.block.try_load_next(Ordering::Acquire)?;
+            self.set_block(next_block);
+        }
+        if self.index != 0 {
+            return None;
+        }
+        let is_full = self.block.bit_blocks().iter()
+            .all(|bit_block| bit_block.load(Ordering::Acquire) == u64::MAX);
+        if !is_full || self.block.has_tombstones() {
+            return None;
+        }
+        let capacity = self.block.capacity;
+        self.index = capacity;
+        self.len   = capacity;
+        self.bitblock_index = capacity / 64;
+        // Block-only readers must be seen by quiesce() and bounded writers too.
+        self.report_position();
+        Some(unsafe{ slice::from_raw_parts(self.block.mem(), capacity) })
+    }
+    
+    /// Same as [next()], but message lives as long as returned guard - 
+    /// not until the next read. So several messages can be held at once.
+    /// 
+    /// ```
+    /// let queue = chute::mpmc::Queue::new();
+    /// let mut reader = queue.reader();
+    /// queue.blocking_push(1);
+    /// queue.blocking_push(2);
+    /// let first  = reader.next_guard().unwrap();
+    /// let second = reader.next_guard().unwrap();
+    /// assert_eq!((*first, *second), (1, 2));
+    /// ```
+    /// 
+    /// Each guard is an atomic increment/decrement of the block use counter. 
+    /// 
+    /// [next()]: LendingReader::next
+    #[inline]
+    pub fn next_guard(&mut self) -> Option<ReadGuard<T>> {
+        let value: *const T = self.next()?;
+        // `value` is in the current block.
+        Some(unsafe{ ReadGuard::new(self.block.clone(), value) })
+    }
+    
+    /// Same as [next()], but with message index - its slot in the queue, 
+    /// reserved by writer. 
+    /// 
+    /// All readers see a message under the same index. It is the [offset()]
+    /// reader had right before reading the message.
+    /// 
+    /// Indices follow slot reservation order. Only messages of the same 
+    /// writer are guaranteed to be in push order. Aborted slots 
+    /// (see [Writer::begin_push()]) are skipped, leaving gaps.
+    /// 
+    /// [next()]: LendingReader::next
+    /// [offset()]: Self::offset
+    #[inline]
+    pub fn next_indexed(&mut self) -> Option<(u64, &T)> {
+        let value: *const T = self.next()?;
+        // Reader is right past the message.
+        let index = (self.position() - 1) as u64;
+        Some((index, unsafe{ &*value }))
+    }
+    
+    /// Ready messages in the current block, from reader's position, as one slice. 
+    /// Reader advances past them.
+    /// 
+    /// Like [next()], but for a run of messages - moves to the next block 
+    /// when the current one is read. Never crosses block boundary or 
+    /// aborted slot (see [Writer::begin_push()]) - the rest of the run 
+    /// comes with the following call. Returns `None` if there is nothing new.
+    /// 
+    /// ```
+    /// # use chute::LendingReader;
+    /// let queue = chute::mpmc::Queue::new();
+    /// let mut reader = queue.reader();
+    /// let mut writer = queue.writer();
+    /// writer.push(1);
+    /// writer.push(2);
+    /// assert_eq!(reader.next_chunk(), Some(&[1, 2][..]));
+    /// assert_eq!(reader.next_chunk(), None);
+    /// ```
+    /// 
+    /// [next()]: LendingReader::next
+    pub fn next_chunk(&mut self) -> Option<&[T]> {
+        // Moves to the next block and steps over aborted slots, if needed.
+        self.next()?;
+        self.unread();
+        let (ptr, len) = self.current_block_slice();
+        let index = self.index + len;
+        self.index = index;
+        if index > self.len {
+            self.len = index;
+            self.bitblock_index = index / 64;
+        }
+        Some(unsafe{ slice::from_raw_parts(ptr, len) })
+    }
+    
+    /// Move to the start of `block`.
+    #[inline]
+    fn set_block(&mut self, block: BlockArc<T>) {
+        let bit_block = unsafe {
+            block.bit_blocks().get_unchecked(0)
+        }.load(Ordering::Acquire);
+        self.counters.bitblock_load();
+        self.counters.block_switch();
+
+        self.block = block;
+        self.index = 0;
+        self.len   = bit_block.trailing_ones() as usize; 
+        self.bitblock_index = (bit_block == u64::MAX) as usize;
+        self.tombstones = self.block.has_tombstones();
+        if let Some(f) = &mut self.on_block_advance {
+            f(self.block.seq as u64);
+        }
+    }
+    
+    /// Converts to [CopyingReader] - cloning reader, that releases
+    /// consumed blocks eagerly.
+    #[must_use]
+    #[inline]
+    pub fn copying(self) -> CopyingReader<Self> {
+        CopyingReader{ reader: self }
+    }
+    
+    /// If current block is read to the end, and there is next one - 
+    /// moves to it, releasing current block.
+    #[inline]
+    fn leave_finished_block(&mut self) {
+        if self.index == self.block.capacity {
+            if let Some(next_block) = self.block.try_load_next(Ordering::Acquire) {
+                self.set_block(next_block);
+            }
+        }
+    }
+    
+    /// Moves unread messages out of the queue, consuming reader.
+    /// 
+    /// Messages are moved out only from blocks, that this reader owns exclusively - 
+    /// iteration stops at the first block, that is shared with some other reader,
+    /// writer or the queue itself. So to salvage all messages, drop the queue,
+    /// all writers and other readers first.
+    /// 
+    /// Messages that are not moved out, are dropped with their blocks as usual.
+    /// 
+    /// Requires `T: Unpin` - moving out would break [pinning] guarantee:
+    /// ```compile_fail
+    /// # use std::marker::PhantomPinned;
+    /// let queue = chute::mpmc::Queue::<PhantomPinned>::new();
+    /// let reader = queue.reader();
+    /// drop(reader.into_drain());
+    /// ```
+    /// 
+    /// [pinning]: crate#pinning
+    #[inline]
+    pub fn into_drain(self) -> impl Iterator<Item = T>
+    where
+        T: Unpin
+    {
+        BlockDrain::new(self.block, self.index)
+    }
+    
+    /// [into_drain()] of each reader, chained - reader by reader, in order.
+    /// 
+    /// For shutdown, with one reader per queue: drop queues and writers, 
+    /// then drain what is left into one output.
+    /// 
+    /// [into_drain()]: Self::into_drain
+    #[inline]
+    pub fn drain_all(readers: impl IntoIterator<Item = Self>) -> impl Iterator<Item = T>
+    where
+        T: Unpin
+    {
+        readers.into_iter().flat_map(Self::into_drain)
+    }
+    
+    /// Steps reader back by one message, so the next [next()] returns it again.
+    ///
+    /// Un-reading the message, most recently returned by [next()], always
+    /// succeeds. You can keep stepping back, but only within the current block -
+    /// returns `false` when reader is at the start of its block.
+    ///
+    /// [next()]: LendingReader::next
+    #[inline]
+    pub fn unread(&mut self) -> bool {
+        if self.index == 0 {
+            return false;
+        }
+        self.index -= 1;
+        true
+    }
+    
+    /// Moves reader forward to `position`, skipping messages in between.
+    /// Does nothing if reader is already there, or past it.
+    /// 
+    /// All messages before `position` must be published. If `position`
+    /// is in a block that does not exist yet - reader stops at the end 
+    /// of the last block.
+    pub(crate) fn skip_to(&mut self, position: usize) {
+        if position <= self.position() {
+            return;
+        }
+        while position >= self.block.start + self.block.capacity {
+            match self.block.try_load_next(Ordering::Acquire) {
+                Some(next_block) => self.set_block(next_block),
+                None => {
+                    let capacity = self.block.capacity;
+                    self.index = capacity;
+                    self.len   = capacity;
+                    self.bitblock_index = capacity / 64;
+                    return;
+                }
+            }
+        }
+        let index = position - self.block.start;
+        self.index = index;
+        if index > self.len {
+            // All messages before `index` are published, so all bitblocks
+            // before its own are full.
+            self.len = index;
+            self.bitblock_index = index / 64;
+        }
+    }
+}
+
+impl<T> Clone for Reader<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self{
+            block: self.block.clone(),
+            index: self.index,
+            len  : self.len,
+            bitblock_index: self.bitblock_index,
+            tombstones: self.tombstones,
+            state: self.state.register_clone(self.position()),
+            on_block_advance: None,
+            counters: Default::default(),
+        }
+    }
+}
+
+impl<T> LendingReader for Reader<T> {
     type Item = T;
 
-    #[inline]
-    fn next(&mut self) -> Option<&T> {
-        if self.index == self.len {
-            if unlikely(self.len == BLOCK_SIZE) {
-                // fetch next block, release current
-                if let Some(next_block) = self.block.try_load_next(Ordering::Acquire) {
-                    let bit_block = unsafe {
-                        next_block.bit_blocks.get_unchecked(0)
-                    }.load(Ordering::Acquire);
+    #[inline]
+    fn next(&mut self) -> Option<&T> {
+        loop {
+            if self.index == self.len {
+                if unlikely(self.len == self.block.capacity) {
+                    // fetch next block, release current
+                    if let Some(next_block) = self.block.try_load_next(Ordering::Acquire) {
+                        self.set_block(next_block);
+                        // For bounded queue writers - do not make them wait 
+                        // till reader runs dry.
+                        self.report_position();
+                        
+                        // TODO: Disallow empty blocks?
+                        if self.len == 0 {
+                            return None;
+                        }
+                    } else {
+                        self.report_position();
+                        return None;
+                    }
+                } else {
+                    // Reread len.
+                    // This is a synchronization point. `mem` data should be in 
+                    // current thread visibility, after an atomic load. 
+                        
+                    let bit_block = unsafe {
+                        self.block.bit_blocks().get_unchecked(self.bitblock_index)
+                    }.load(Ordering::Acquire);
+                    self.counters.bitblock_load();
+                    
+                    let new_len = self.bitblock_index*64 + bit_block.trailing_ones() as usize;
+                    
+                    if self.len == new_len {
+                        // nothing changed.
+                        self.report_position();
+                        return None;
+                    } 
+                    
+                    // Switch to next bitblock.
+                    // Do not check for >=capacity. That will happen later.
+                    if bit_block == u64::MAX {
+                        self.bitblock_index += 1;
+                    }
+                    
+                    self.len = new_len;
+                    self.tombstones = self.block.has_tombstones();
+                }
+            }
+            
+            let index = self.index;
+            self.index += 1;
+            if unlikely(self.tombstones) && self.block.is_tombstone(index) {
+                continue;
+            }
+            return Some(unsafe{ &*self.block.mem().add(index) });
+        }
+    }
+}
+
+impl<T> PeekingReader for Reader<T> {
+    #[inline]
+    fn peek(&mut self) -> Option<&T> {
+        self.next()?;
+        self.unread();
+        Some(unsafe{ &*self.block.mem().add(self.index) })
+    }
+}
+
+
+#[cfg(feature = "async")]
+impl<T: Clone> futures_core::Stream for crate::ReaderStream<Reader<T>> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut core::task::Context<'_>) 
+        -> core::task::Poll<Option<T>> 
+    {
+        use core::task::Poll;
+        let reader = &mut self.get_mut().reader;
+        loop {
+            match reader.try_next() {
+                Ok(Some(value)) => return Poll::Ready(Some(value.clone())),
+                Ok(None) => {},
+                Err(_) => return Poll::Ready(None),
+            }
+            let subscriptions = reader.state.subscriptions();
+            subscriptions.message_notifier.register(cx.waker());
+            // Re-check after registration, in case push was before it.
+            if !reader.has_news() && !subscriptions.is_closed() {
+                return Poll::Pending;
+            }
+        }
+    }
+}
+
+impl<T: Clone> Iterator for CopyingReader<Reader<T>> {
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        // Clone before advancing - panicking clone must not lose the message.
+        let value = self.reader.peek().cloned();
+        if value.is_some() {
+            self.reader.next();
+        }
+        self.reader.leave_finished_block();
+        value
+    }
+}
+
+
+#[cfg(test)]
+mod test_mpmc{
+    use std::sync::{Arc, Mutex};
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use itertools::assert_equal;
+    use rand::{Rng, SeedableRng};
+    use crate::block::BLOCK_SIZE;
+    use crate::{LendingReader, PeekingReader};
+    use crate::mpmc::{BufWriter, Queue, Reader};
+    use crate::test::{CountingAllocator, StringWrapper};
+
+    #[test]
+    fn test_mpmc() {
+        let queue: Arc<Queue<usize>> = Default::default();
+        let mut reader = queue.reader();
+        //let mut writer = queue.writer();
+        
+        const COUNT: usize = BLOCK_SIZE * 4; 
+        for i in 0..COUNT {
+            queue.blocking_push(i);
+            //writer.push(i);    
+        }
+        
+        let mut vec = Vec::new();
+        while let Some(value) = reader.next() {
+            vec.push(*value);
+        }
+        assert_equal(vec, 0..COUNT);
+    }
+    
+    #[test]
+    fn unread_test() {
+        let queue: Arc<Queue<usize>> = Default::default();
+        let mut reader = queue.reader();
+        assert!(!reader.unread());
+
+        for i in 0..BLOCK_SIZE+1 {
+            queue.blocking_push(i);
+        }
+        assert_eq!(reader.next(), Some(&0));
+        assert!(reader.unread());
+        assert_eq!(reader.next(), Some(&0));
+
+        for i in 1..BLOCK_SIZE {
+            assert_eq!(reader.next(), Some(&i));
+        }
+        assert_eq!(reader.next(), Some(&BLOCK_SIZE));
+        assert!(reader.unread());
+        assert!(!reader.unread());
+        assert_eq!(reader.next(), Some(&BLOCK_SIZE));
+        assert_eq!(reader.next(), None);
+    }
+
+    #[test]
+    fn try_next_test() {
+        use crate::Error;
+        
+        let queue: Arc<Queue<usize>> = Default::default();
+        let mut reader = queue.reader();
+        let mut writer = queue.writer();
+        assert_eq!(reader.try_next(), Ok(None));
+        drop(queue);
+        
+        // Writer keeps queue alive.
+        writer.push(1);
+        assert_eq!(reader.try_next(), Ok(Some(&1)));
+        assert_eq!(reader.try_next(), Ok(None));
+        
+        writer.push(2);
+        drop(writer);
+        assert_eq!(reader.try_next(), Ok(Some(&2)));
+        assert_eq!(reader.try_next(), Err(Error::Closed));
+    }
+    
+    #[cfg(feature = "async")]
+    #[test]
+    fn stream_test() {
+        use std::task::{Context, Poll, Waker};
+        use futures_core::Stream;
+        use crate::test::CountingWaker;
+        
+        let queue: Arc<Queue<usize>> = Default::default();
+        const COUNT: usize = BLOCK_SIZE * 2 + 10;
+        let waker: Arc<CountingWaker> = Default::default();
+        let reader = queue.reader();
+        let join = std::thread::spawn({
+            let waker = waker.clone();
+            move || {
+                let std_waker = Waker::from(waker.clone());
+                let mut cx = Context::from_waker(&std_waker);
+                let mut stream = reader.into_stream();
+                let mut values = Vec::new();
+                loop {
+                    // Poll again only when woken. 
+                    let wakes = waker.wakes.load(Ordering::SeqCst);
+                    match std::pin::Pin::new(&mut stream).poll_next(&mut cx) {
+                        Poll::Ready(Some(value)) => values.push(value),
+                        Poll::Ready(None) => return values,
+                        Poll::Pending => while waker.wakes.load(Ordering::SeqCst) == wakes {
+                            std::thread::yield_now();
+                        }
+                    }
+                }
+            }
+        });
+        
+        let mut writer = queue.writer();
+        drop(queue);
+        for i in 0..COUNT {
+            writer.push_notify(i);
+        }
+        drop(writer);
+        assert_equal(join.join().unwrap(), 0..COUNT);
+    }
+    
+    #[test]
+    #[cfg(feature = "std")]
+    fn recv_test() {
+        let queue: Arc<Queue<usize>> = Default::default();
+        const COUNT: usize = BLOCK_SIZE * 2 + 10;
+        let readers: Vec<_> = (0..2).map(|_| {
+            let mut reader = queue.reader();
+            std::thread::spawn(move || {
+                let mut values = Vec::new();
+                while let Some(value) = reader.recv() {
+                    values.push(*value);
+                }
+                values
+            })
+        }).collect();
+        
+        let mut writer = queue.writer();
+        drop(queue);
+        for i in 0..COUNT {
+            if i % 100 == 0 {
+                // Let readers fall asleep.
+                std::thread::sleep(std::time::Duration::from_millis(1));
+            }
+            writer.push_notify(i);
+        }
+        drop(writer);
+        
+        for reader in readers {
+            assert_equal(reader.join().unwrap(), 0..COUNT);
+        }
+    }
+    
+    #[test]
+    fn current_block_slice_test() {
+        let queue: Arc<Queue<usize>> = Default::default();
+        let mut reader = queue.reader();
+        for i in 0..BLOCK_SIZE+10 {
+            queue.blocking_push(i);
+        }
+        assert_eq!(reader.next(), Some(&0));
+        
+        let (ptr, len) = reader.current_block_slice();
+        let slice = unsafe{ std::slice::from_raw_parts(ptr, len) };
+        assert_equal(slice.iter().copied(), 1..BLOCK_SIZE);
+        
+        for _ in 0..BLOCK_SIZE {
+            reader.next();
+        }
+        let (ptr, len) = reader.current_block_slice();
+        let slice = unsafe{ std::slice::from_raw_parts(ptr, len) };
+        assert_equal(slice.iter().copied(), BLOCK_SIZE+1..BLOCK_SIZE+10);
+    }
+
+    #[test]
+    fn block_remaining_iter_test() {
+        let queue: Arc<Queue<usize>> = Default::default();
+        let mut reader = queue.reader();
+        for i in 0..BLOCK_SIZE+10 {
+            queue.blocking_push(i);
+        }
+        assert_eq!(reader.next(), Some(&0));
+        
+        let iter = reader.block_remaining_iter();
+        assert_eq!(iter.len(), BLOCK_SIZE-1);
+        assert_equal(iter.copied(), 1..BLOCK_SIZE);
+        // Does not cross block boundary.
+        assert_eq!(reader.block_remaining_iter().len(), 0);
+        
+        assert_eq!(reader.next(), Some(&BLOCK_SIZE));
+        let iter = reader.block_remaining_iter();
+        assert_equal(iter.copied(), BLOCK_SIZE+1..BLOCK_SIZE+10);
+        assert_eq!(reader.next(), None);
+        
+        queue.blocking_push(100);
+        assert_eq!(reader.next(), Some(&100));
+    }
+
+    #[test]
+    fn copying_reader_test() {
+        let queue: Arc<Queue<usize>> = Default::default();
+        let mut reader = queue.reader().copying();
+        for i in 0..BLOCK_SIZE+1 {
+            queue.blocking_push(i);
+        }
+        assert_equal(reader.by_ref().take(BLOCK_SIZE), 0..BLOCK_SIZE);
+        
+        let reader = reader.into_inner();
+        let mut blocks = 0;
+        reader.for_each_block(|_| blocks += 1);
+        assert_eq!(blocks, 1);
+        assert_equal(reader.copying(), [BLOCK_SIZE]);
+    }
+
+    #[test]
+    fn push_async_test() {
+        use std::future::Future;
+        use std::pin::pin;
+        use std::task::{Context, Poll, Waker};
+        
+        let queue: Arc<Queue<usize>> = Default::default();
+        let mut reader = queue.reader();
+        let mut cx = Context::from_waker(Waker::noop());
+        
+        let mut future = pin!(queue.push_async(1));
+        let lock = queue.lock_last_block();
+        assert_eq!(future.as_mut().poll(&mut cx), Poll::Pending);
+        assert_eq!(reader.next(), None);
+        drop(lock);
+        assert_eq!(future.as_mut().poll(&mut cx), Poll::Ready(()));
+        assert_eq!(reader.next(), Some(&1));
+        
+        for i in 0..BLOCK_SIZE+10 {
+            assert_eq!(pin!(queue.push_async(i)).poll(&mut cx), Poll::Ready(()));
+        }
+        assert_equal(reader.cloned(), 0..BLOCK_SIZE+10);
+    }
+    
+    #[cfg(feature = "async")]
+    #[test]
+    fn bounded_push_async_test() {
+        use std::future::Future;
+        use std::pin::pin;
+        use std::task::{Context, Poll, Waker};
+        use crate::test::CountingWaker;
+        
+        let queue: Arc<Queue<usize>> = Queue::bounded(2);
+        let mut reader = queue.reader();
+        let waker: Arc<CountingWaker> = Default::default();
+        let std_waker = Waker::from(waker.clone());
+        let mut cx = Context::from_waker(&std_waker);
+        
+        queue.blocking_push(0);
+        queue.blocking_push(1);
+        let mut future = pin!(queue.push_async(2));
+        assert_eq!(future.as_mut().poll(&mut cx), Poll::Pending);
+        assert_eq!(future.as_mut().poll(&mut cx), Poll::Pending);
+        // Writer waits for reader, instead of busy polling.
+        assert_eq!(waker.wakes.load(Ordering::SeqCst), 0);
+        
+        assert_eq!(reader.next(), Some(&0));
+        assert_eq!(reader.next(), Some(&1));
+        assert_eq!(waker.wakes.load(Ordering::SeqCst), 0);
+        // Reader reports, as it runs dry.
+        assert_eq!(reader.next(), None);
+        // Registered once.
+        assert_eq!(waker.wakes.load(Ordering::SeqCst), 1);
+        assert_eq!(future.as_mut().poll(&mut cx), Poll::Ready(()));
+        assert_eq!(reader.next(), Some(&2));
+    }
+    
+    #[test]
+    fn prefault_keeps_messages_test() {
+        let queue: Queue<u32> = Queue::default();
+        let mut reader = queue.reader();
+        queue.blocking_push(0xDEADBEEF);
+        let queue = queue.with_prefault(true);
+        queue.blocking_push(1);
+        assert_eq!(reader.next(), Some(&0xDEADBEEF));
+        assert_eq!(reader.next(), Some(&1));
+    }
+    
+    #[test]
+    fn block_allocator_test() {
+        let allocator: Arc<CountingAllocator> = Default::default();
+        let queue: Arc<Queue<StringWrapper>> = Arc::new(
+            Queue::default().with_block_allocator(allocator.clone())
+        );
+        let mut reader = queue.reader();
+        let mut writer = queue.writer();
+        for i in 0..BLOCK_SIZE*2 + 10 {
+            writer.push(i.into());
+        }
+        assert_eq!(allocator.allocs.load(Ordering::Relaxed), 3);
+        assert_eq!(usize::from(reader.next().unwrap().clone()), 0);
+        
+        // Tombstones as well.
+        drop(writer.begin_push());
+        assert_eq!(allocator.allocs.load(Ordering::Relaxed), 4);
+        
+        drop((queue, reader, writer));
+        assert_eq!(allocator.deallocs.load(Ordering::Relaxed), 4);
+    }
+    
+    #[test]
+    fn next_lock_free_test() {
+        use std::sync::mpsc;
+        use std::time::Duration;
+        
+        let queue: Arc<Queue<usize>> = Default::default();
+        let mut reader = queue.reader();
+        for i in 0..BLOCK_SIZE*2 + 10 {
+            queue.blocking_push(i);
+        }
+        
+        // Reading should not take any of queue locks.
+        let locks = queue.subscriptions.lock_all();
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            let mut count = 0;
+            while reader.next().is_some() {
+                count += 1;
+            }
+            sender.send(count).unwrap();
+        });
+        let count = receiver.recv_timeout(Duration::from_secs(10))
+            .expect("Reader blocked on lock.");
+        assert_eq!(count, BLOCK_SIZE*2 + 10);
+        drop(locks);
+    }
+    
+    #[test]
+    fn reader_lifecycle_lock_free_test() {
+        use std::sync::mpsc;
+        use std::time::Duration;
+        
+        let queue: Arc<Queue<usize>> = Default::default();
+        let _early = queue.reader();
+        
+        // Constructing, cloning and dropping readers should not take any of queue locks.
+        let locks = queue.subscriptions.lock_all();
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn({
+            let queue = queue.clone();
+            move || {
+                for _ in 0..10 {
+                    let reader = queue.reader();
+                    let clone = reader.clone();
+                    drop(reader);
+                    drop(clone);
+                }
+                sender.send(()).unwrap();
+            }
+        });
+        receiver.recv_timeout(Duration::from_secs(10))
+            .expect("Reader lifecycle blocked on lock.");
+        drop(locks);
+        assert_eq!(queue.reader_count(), 1);
+    }
+    
+    #[test]
+    #[should_panic(expected = "Group does not fit in queue bound.")]
+    fn bounded_push_group_test() {
+        let queue: Arc<Queue<usize>> = Queue::bounded(10);
+        let _reader = queue.reader();
+        let mut writer = queue.writer();
+        writer.push_group(&mut (0..11).collect());
+    }
+    
+    #[test]
+    fn bounded_test() {
+        use std::time::Duration;
+        
+        const BOUND: usize = BLOCK_SIZE + 10;
+        let queue = Queue::bounded(BOUND);
+        let mut reader = queue.reader();
+        let mut writer = queue.writer();
+        for i in 0..BOUND {
+            writer.push(i);
+        }
+        
+        let pushed = AtomicBool::new(false);
+        std::thread::scope(|s| {
+            s.spawn(|| {
+                writer.push(BOUND);
+                pushed.store(true, Ordering::Release);
+            });
+            std::thread::sleep(Duration::from_millis(50));
+            assert!(!pushed.load(Ordering::Acquire));
+            
+            for i in 0..BOUND {
+                assert_eq!(reader.next(), Some(&i));
+            }
+            // Reader reports progress.
+            while reader.next().is_none() {}
+        });
+        assert!(pushed.load(Ordering::Acquire));
+        assert_eq!(reader.next(), None);
+    }
+    
+    #[test]
+    fn bounded_mt_test() {
+        const BOUND: usize = 100;
+        const COUNT: usize = BLOCK_SIZE * 4;
+        let queue = Queue::bounded(BOUND);
+        let mut reader = queue.reader();
+        std::thread::scope(|s| {
+            for _ in 0..2 {
+                let mut writer = queue.writer();
+                s.spawn(move || {
+                    for i in 0..COUNT {
+                        writer.push(i);
+                    }
+                });
+            }
+            let mut count = 0;
+            while count != COUNT*2 {
+                if reader.next().is_some() {
+                    count += 1;
+                }
+            }
+        });
+    }
+    
+    #[test]
+    fn seek_test() {
+        use crate::{Error, ReaderOffset};
+        
+        let queue: Arc<Queue<usize>> = Default::default();
+        let mut retention = queue.reader();
+        let mut reader = retention.clone();
+        let mut writer = queue.writer();
+        for i in 0..BLOCK_SIZE*2 {
+            writer.push(i);
+        }
+        for _ in 0..BLOCK_SIZE+5 {
+            reader.next();
+        }
+        let offset = ReaderOffset::from_bytes(reader.offset().to_bytes());
+        assert_eq!(offset, ReaderOffset((BLOCK_SIZE+5) as u64));
+        
+        // Past published.
+        let slot = writer.begin_push();
+        assert_eq!(retention.seek(ReaderOffset((BLOCK_SIZE*2 + 1) as u64)), Err(Error::OffsetUnavailable));
+        drop(slot);
+        
+        retention.seek(offset).unwrap();
+        assert_eq!(retention.next(), Some(&(BLOCK_SIZE+5)));
+        // Behind.
+        assert_eq!(retention.seek(offset), Err(Error::OffsetUnavailable));
+        
+        retention.seek(ReaderOffset((BLOCK_SIZE*2) as u64)).unwrap();
+        assert_eq!(retention.next(), None);
+        writer.push(100);
+        assert_eq!(retention.next(), Some(&100));
+    }
+    
+    #[test]
+    fn snapshot_range_test() {
+        use crate::{Error, ReaderOffset};
+        
+        let queue: Arc<Queue<usize>> = Default::default();
+        let mut reader = queue.reader();
+        let mut writer = queue.writer();
+        for i in 0..BLOCK_SIZE {
+            writer.push(i);
+        }
+        drop(writer.begin_push());
+        writer.push(BLOCK_SIZE);
+        let mut writer2 = queue.writer();
+        let slot = writer2.begin_push();
+        writer.push(BLOCK_SIZE+1);
+        
+        // Aborted skipped. In-flight not reached.
+        let end = ReaderOffset((BLOCK_SIZE+2) as u64);
+        let values = reader.snapshot_range(ReaderOffset(1), end).unwrap();
+        assert!(values.iter().copied().eq(1..BLOCK_SIZE+1));
+        assert_eq!(reader.snapshot_range(ReaderOffset(1), ReaderOffset((BLOCK_SIZE+3) as u64)), Err(Error::OffsetUnavailable));
+        drop(slot);
+        
+        assert_eq!(reader.next(), Some(&0));
+        assert_eq!(reader.next(), Some(&1));
+        assert_eq!(reader.snapshot_range(ReaderOffset(1), end), Err(Error::OffsetUnavailable));
+    }
+    
+    #[test]
+    fn on_first_overflow_test() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let queue: Arc<Queue<usize>> = Default::default();
+        queue.on_first_overflow({
+            let calls = calls.clone();
+            move || { calls.fetch_add(1, Ordering::Relaxed); }
+        });
+        let mut writer = queue.writer();
+        for i in 0..BLOCK_SIZE {
+            writer.push(i);
+        }
+        assert_eq!(calls.load(Ordering::Relaxed), 0);
+        for i in 0..BLOCK_SIZE {
+            queue.blocking_push(i);
+            writer.push(i);
+        }
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+    }
+    
+    #[test]
+    fn lock_unwind_test() {
+        let queue: Arc<Queue<usize>> = Default::default();
+        let reader = queue.reader();
+        queue.blocking_push(0);
+        
+        let result = std::thread::scope(|s| s.spawn(|| {
+            let _lock = queue.lock_last_block();
+            panic!("panic in critical section");
+        }).join());
+        assert!(result.is_err());
+        
+        // Not deadlocked.
+        let mut writer = queue.writer();
+        for i in 1..BLOCK_SIZE+10 {
+            if i % 2 == 0 { writer.push(i) } else { queue.blocking_push(i) }
+        }
+        assert_equal(reader.cloned(), 0..BLOCK_SIZE+10);
+    }
+
+    #[test]
+    fn into_drain_test() {
+        const COUNT: usize = BLOCK_SIZE + 10;
+        let queue: Arc<Queue<StringWrapper>> = Default::default();
+        let mut reader = queue.reader();
+        let mut writer = queue.writer();
+        for i in 0..COUNT {
+            writer.push(i.into());
+        }
+        reader.next();
+        drop(writer);
+        drop(queue);
+        
+        let drained = reader.into_drain().map(usize::from);
+        assert_equal(drained, 1..COUNT);
+    }
+
+    #[test]
+    fn drain_all_test() {
+        let queues: Vec<Arc<Queue<StringWrapper>>> = (0..3).map(|_| Default::default()).collect();
+        let readers: Vec<_> = queues.iter().map(|queue| queue.reader()).collect();
+        for (i, queue) in queues.iter().enumerate() {
+            for j in 0..BLOCK_SIZE {
+                queue.blocking_push((i*BLOCK_SIZE + j).into());
+            }
+        }
+        drop(queues);
+        
+        let drained = Reader::drain_all(readers).map(usize::from);
+        assert_equal(drained, 0..BLOCK_SIZE*3);
+    }
+
+    #[test]
+    fn next_block_test() {
+        let queue: Arc<Queue<usize>> = Default::default();
+        let mut reader = queue.reader();
+        for i in 0..BLOCK_SIZE*2 + 10 {
+            queue.blocking_push(i);
+        }
+        assert_equal(reader.next_block().unwrap().iter().copied(), 0..BLOCK_SIZE);
+        assert_equal(reader.next_block().unwrap().iter().copied(), BLOCK_SIZE..BLOCK_SIZE*2);
+        // Not full.
+        assert_eq!(reader.next_block(), None);
+        assert_eq!(reader.next(), Some(&(BLOCK_SIZE*2)));
+        assert_eq!(reader.next_block(), None);
+    }
+
+    #[test]
+    fn next_guard_test() {
+        let queue: Arc<Queue<StringWrapper>> = Default::default();
+        let mut reader = queue.reader();
+        for i in 0..BLOCK_SIZE*2 {
+            queue.blocking_push(i.into());
+        }
+        let first = reader.next_guard().unwrap();
+        let guards: Vec<_> = std::iter::from_fn(|| reader.next_guard()).collect();
+        drop((queue, reader));
+        
+        // Guards outlive reader, queue, and blocks they were read from.
+        assert_eq!(usize::from((*first).clone()), 0);
+        assert!(guards.iter().map(|guard| usize::from((**guard).clone())).eq(1..BLOCK_SIZE*2));
+    }
+
+    #[test]
+    fn next_chunk_test() {
+        let queue: Arc<Queue<usize>> = Default::default();
+        let mut reader = queue.reader();
+        assert_eq!(reader.next_chunk(), None);
+        for i in 0..BLOCK_SIZE+10 {
+            queue.blocking_push(i);
+        }
+        assert_eq!(reader.next(), Some(&0));
+        // Stops at block end.
+        assert_equal(reader.next_chunk().unwrap().iter().copied(), 1..BLOCK_SIZE);
+        assert_equal(reader.next_chunk().unwrap().iter().copied(), BLOCK_SIZE..BLOCK_SIZE+10);
+        assert_eq!(reader.next_chunk(), None);
+        
+        // Stops at aborted slot, and steps over it.
+        let mut writer = queue.writer();
+        writer.push(1);
+        drop(writer.begin_push());
+        writer.push(3);
+        writer.push(4);
+        assert_eq!(reader.next_chunk(), Some(&[1][..]));
+        assert_eq!(reader.next_chunk(), Some(&[3, 4][..]));
+        assert_eq!(reader.next(), None);
+    }
+
+    #[test]
+    fn on_block_advance_test() {
+        let queue: Arc<Queue<usize>> = Default::default();
+        let mut reader = queue.reader();
+        let seqs: Arc<Mutex<Vec<u64>>> = Default::default();
+        reader.on_block_advance({
+            let seqs = seqs.clone();
+            move |seq| seqs.lock().unwrap().push(seq)
+        });
+        for i in 0..BLOCK_SIZE*2 + 1 {
+            queue.blocking_push(i);
+        }
+        let mut clone = reader.clone();
+        while reader.next().is_some() {}
+        assert_eq!(*seqs.lock().unwrap(), [1, 2]);
+        
+        // Clones do not inherit callback.
+        while clone.next().is_some() {}
+        assert_eq!(*seqs.lock().unwrap(), [1, 2]);
+    }
+
+    #[test]
+    fn buf_writer_test() {
+        let queue: Arc<Queue<StringWrapper>> = Default::default();
+        let mut reader = queue.reader();
+        
+        {
+            let mut writer = BufWriter::new(&queue);
+            writer.push(0.into());
+            writer.push(1.into());
+            assert_eq!(writer.pending(), 2);
+            assert!(reader.next().is_none());
+            writer.flush();
+            writer.push(2.into());
+        }
+        {
+            let mut writer = BufWriter::new(&queue).flush_on_drop(false);
+            writer.push(3.into());
+            writer.push(4.into());
+        }
+        
+        let values: Vec<usize> = reader.cloned().map(Into::into).collect();
+        assert_eq!(values, [0, 1, 2]);
+    }
+
+    #[test]
+    fn catch_up_count_test() {
+        let queue: Arc<Queue<usize>> = Default::default();
+        let mut reader = queue.reader();
+        assert_eq!(reader.catch_up_count(&queue), 0);
+        
+        for i in 0..BLOCK_SIZE+10 {
+            queue.blocking_push(i);
+        }
+        assert_eq!(reader.catch_up_count(&queue), BLOCK_SIZE+10);
+        reader.next();
+        assert_eq!(reader.catch_up_count(&queue), BLOCK_SIZE+9);
+        while reader.next().is_some() {}
+        assert_eq!(reader.catch_up_count(&queue), 0);
+    }
+
+    #[test]
+    fn scan_hint_test() {
+        let queue: Arc<Queue<usize>> = Default::default();
+        const START: usize = BLOCK_SIZE/2 + 10;
+        const END  : usize = BLOCK_SIZE - 10;
+        for i in 0..START {
+            queue.blocking_push(i);
+        }
+        
+        // Starts scan from its position, not from the block start.
+        let mut reader = queue.reader();
+        assert_eq!(reader.bitblock_index, START/64);
+        
+        for i in START..END {
+            queue.blocking_push(i);
+        }
+        for i in START..END {
+            assert_eq!(reader.next(), Some(&i));
+        }
+        assert_eq!(reader.next(), None);
+        assert_eq!(reader.bitblock_index, END/64);
+    }
+
+    #[test]
+    fn advance_to_test() {
+        let queue: Arc<Queue<usize>> = Default::default();
+        let mut reader1 = queue.reader();
+        for i in 0..BLOCK_SIZE*2 {
+            queue.blocking_push(i);
+        }
+        let mut reader2 = reader1.clone();
+        for _ in 0..BLOCK_SIZE+10 {
+            reader2.next();
+        }
+        
+        reader2.advance_to(&reader1);
+        assert_eq!(reader2.next(), Some(&(BLOCK_SIZE+10)));
+        
+        reader1.advance_to(&reader2);
+        assert_eq!(reader1.next(), Some(&(BLOCK_SIZE+11)));
+    }
 
-                    self.block = next_block;
-                    self.index = 0;
-                    self.len   = bit_block.trailing_ones() as usize; 
-                    self.bitblock_index = (bit_block == u64::MAX) as usize;
-                    
-                    // TODO: Disallow empty blocks?
-                    if self.len == 0 {
-                        return None;
-                    }
-                } else {
-                    return None;
-                }
-            } else {
-                // Reread len.
-                // This is a synchronization point. `mem` data should be in 
-                // current thread visibility, after an atomic load. 
-                    
-                let bit_block = unsafe {
-                    self.block.bit_blocks.get_unchecked(self.bitblock_index)
-                }.load(Ordering::Acquire);
-                
-                let new_len = self.bitblock_index*64 + bit_block.trailing_ones() as usize;
-                
-                if self.len == new_len {
-                    // nothing changed.
-                    return None;
-                } 
-                
-                // Switch to next bitblock.
-                // Do not check for >=BLOCK_SIZE. That will happen later.
-                if bit_block == u64::MAX {
-                    self.bitblock_index = self.bitblock_index + 1;
+    #[test]
+    fn writer_handoff_test() {
+        fn assert_send<S: Send>(_: &S) {}
+        
+        let queue: Arc<Queue<usize>> = Default::default();
+        let mut reader = queue.reader();
+        let mut writer = queue.writer();
+        assert_send(&writer);
+        
+        const COUNT: usize = BLOCK_SIZE + 10;
+        for i in 0..COUNT/2 {
+            writer.push(i);
+        }
+        let mut writer = std::thread::spawn(move || {
+            for i in COUNT/2..COUNT {
+                writer.push(i);
+            }
+            writer
+        }).join().unwrap();
+        writer.push(COUNT);
+        
+        let mut values = Vec::new();
+        while let Some(value) = reader.next() {
+            values.push(*value);
+        }
+        assert_equal(values, 0..=COUNT);
+    }
+
+    fn test_push_group(queue: Arc<Queue<(usize, usize, usize)>>) {
+        const WRITERS: usize = 2;
+        const GROUPS : usize = if cfg!(miri) {10} else {200};
+        
+        // (group, index in group, group len)
+        let mut reader = queue.reader();
+        let join_reader = std::thread::spawn(move || {
+            let mut groups = 0;
+            while groups != WRITERS * GROUPS {
+                let Some(&(group, index, len)) = reader.next() else { continue };
+                assert_eq!(index, 0);
+                for i in 1..len {
+                    // Group is visible as a whole - no None in between.
+                    assert_eq!(reader.next(), Some(&(group, i, len)));
                 }
-                
-                self.len = new_len;
+                groups += 1;
             }
+        });
+        
+        let mut join_writers = Vec::new();
+        for w in 0..WRITERS {
+            let mut writer = queue.writer();
+            join_writers.push(std::thread::spawn(move || {
+                let mut rng = rand::rngs::StdRng::seed_from_u64(w as u64);
+                let mut values = Vec::new();
+                for g in 0..GROUPS {
+                    let group = w * GROUPS + g;
+                    let len = rng.gen_range(1..=BLOCK_SIZE);
+                    values.extend((0..len).map(|i| (group, i, len)));
+                    writer.push_group(&mut values);
+                    assert!(values.is_empty());
+                }
+            }));
         }
         
-        unsafe{
-            let value = &*self.block.mem().add(self.index);
-            self.index += 1;
-            Some(value)
+        for join in join_writers {
+            join.join().unwrap();
         }
+        join_reader.join().unwrap();
+    }
+    
+    #[test]
+    fn push_group_test() {
+        test_push_group(Default::default());
+        #[cfg(feature = "std")]
+        test_push_group(Arc::new(Queue::default().with_adaptive_blocks()));
     }
-}
 
+    #[test]
+    #[cfg(feature = "std")]
+    fn push_iter_test() {
+        const COUNT: usize = BLOCK_SIZE * 2 + 10;
+        let queue: Arc<Queue<usize>> = Default::default();
+        let mut reader = queue.reader();
+        let mut writer = queue.writer();
+        writer.push(0);
+        // Straddles block boundaries.
+        writer.push_iter(1..COUNT);
+        writer.push_iter(std::iter::empty());
+        assert_equal(std::iter::from_fn(|| reader.next().copied()), 0..COUNT);
+        
+        // Chunks fit in bound.
+        let queue = Queue::<usize>::bounded(10);
+        let mut reader = queue.reader();
+        let mut writer = queue.writer();
+        let join_reader = std::thread::spawn(move || {
+            assert_equal(std::iter::from_fn(|| reader.recv().copied()).take(COUNT), 0..COUNT);
+        });
+        writer.push_iter(0..COUNT);
+        join_reader.join().unwrap();
+    }
 
-#[cfg(test)]
-mod test_mpmc{
-    use std::sync::Arc;
-    use std::sync::atomic::{AtomicUsize, Ordering};
-    use itertools::assert_equal;
-    use rand::{Rng, SeedableRng};
-    use crate::block::BLOCK_SIZE;
-    use crate::LendingReader;
-    use crate::mpmc::Queue;
-    use crate::test::StringWrapper;
+    #[test]
+    fn begin_push_test() {
+        const COUNT: usize = BLOCK_SIZE * 2 + 10;
+        let kept = |i: &usize| !i.is_multiple_of(3);
+        
+        let queue: Arc<Queue<StringWrapper>> = Default::default();
+        let mut reader = queue.reader();
+        let mut writer = queue.writer();
+        for i in 0..COUNT {
+            let slot = writer.begin_push();
+            if kept(&i) {
+                slot.write(i.into());
+            }
+        }
+        assert_equal(
+            std::iter::from_fn(|| reader.next().cloned()).map(usize::from),
+            (0..COUNT).filter(kept)
+        );
+        
+        // Aborted slots are not dropped or drained.
+        drop(reader);
+        let reader = queue.reader();
+        for i in 0..COUNT {
+            let slot = writer.begin_push();
+            if kept(&i) {
+                slot.write(i.into());
+            }
+        }
+        drop(queue);
+        drop(writer);
+        assert_equal(reader.into_drain().map(usize::from), (0..COUNT).filter(kept));
+    }
+    
+    #[test]
+    fn push_in_place_test() {
+        let queue: Arc<Queue<StringWrapper>> = Default::default();
+        let mut reader = queue.reader();
+        let mut writer = queue.writer();
+        unsafe{
+            writer.push_in_place(|ptr| ptr.write(1.into()));
+        }
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| unsafe{
+            writer.push_in_place(|_| panic!("init failed"));
+        }));
+        assert!(result.is_err());
+        unsafe{
+            writer.push_in_place(|ptr| ptr.write(3.into()));
+        }
+        assert_equal(
+            std::iter::from_fn(|| reader.next().cloned()).map(usize::from),
+            [1, 3]
+        );
+    }
+    
+    #[test]
+    fn peek_test() {
+        let queue: Arc<Queue<usize>> = Default::default();
+        let mut reader = queue.reader();
+        let mut writer = queue.writer();
+        assert_eq!(reader.peek(), None);
+        drop(writer.begin_push());
+        writer.push(1);
+        assert_eq!(reader.peek(), Some(&1));
+        assert_eq!(reader.peek(), Some(&1));
+        assert_eq!(reader.next(), Some(&1));
+        assert_eq!(reader.peek(), None);
+    }
+    
+    #[test]
+    fn begin_push_mt_test() {
+        const WRITERS: usize = 2;
+        const COUNT: usize = if cfg!(miri) {200} else {BLOCK_SIZE * 4};
+        
+        let queue: Arc<Queue<(usize, usize)>> = Default::default();
+        let mut reader = queue.reader();
+        let join_reader = std::thread::spawn(move || {
+            let mut last = [None; WRITERS];
+            let mut count = 0;
+            while count != WRITERS * COUNT/2 {
+                let Some(&(w, i)) = reader.next() else { continue };
+                assert!(i.is_multiple_of(2));
+                assert!(last[w] < Some(i));
+                last[w] = Some(i);
+                count += 1;
+            }
+        });
+        
+        let mut join_writers = Vec::new();
+        for w in 0..WRITERS {
+            let mut writer = queue.writer();
+            join_writers.push(std::thread::spawn(move || {
+                for i in 0..COUNT {
+                    let slot = writer.begin_push();
+                    if i.is_multiple_of(2) {
+                        slot.write((w, i));
+                    }
+                }
+            }));
+        }
+        
+        for join in join_writers {
+            join.join().unwrap();
+        }
+        join_reader.join().unwrap();
+    }
 
+    #[cfg(feature = "metrics")]
     #[test]
-    fn test_mpmc() {
+    fn metrics_test() {
+        let queue: Arc<Queue<usize>> = Default::default();
+        let mut writer1 = queue.writer();
+        let mut writer2 = queue.writer();
+        for i in 0..BLOCK_SIZE {
+            writer1.push(i);
+        }
+        assert_eq!(queue.metrics(), Default::default());
+        
+        writer1.push(0);
+        writer2.push(0);
+        let metrics = queue.metrics();
+        assert_eq!(metrics.full_block_pushes, 2);
+        assert_eq!(metrics.blocks_allocated, 1);
+        // writer2 found block, inserted by writer1.
+        assert_eq!(metrics.blocks_found, 1);
+        assert_eq!(metrics.writer_update_fallbacks, 0);
+    }
+    
+    #[cfg(feature = "metrics")]
+    #[test]
+    fn diagnostics_test() {
         let queue: Arc<Queue<usize>> = Default::default();
         let mut reader = queue.reader();
-        //let mut writer = queue.writer();
+        let mut writer = queue.writer();
+        for i in 0..BLOCK_SIZE*2 {
+            writer.push(i);
+        }
+        while reader.next().is_some() {}
+        let diagnostics = reader.diagnostics();
+        assert_eq!(diagnostics.block_switches, 1);
+        // Full blocks - one load per bitblock.
+        assert_eq!(diagnostics.bitblock_loads, (BLOCK_SIZE/64) as u64 * 2);
+        
+        // Caught writer mid-block.
+        writer.push(0);
+        reader.next();
+        writer.push(0);
+        reader.next();
+        assert_eq!(reader.diagnostics().bitblock_loads, diagnostics.bitblock_loads + 2);
+    }
+    
+    #[test]
+    fn full_block_overshoot_test() {
+        const WRITERS: usize = 16;
+        const ROUNDS : usize = if cfg!(miri) {2} else {50};
+        
+        let queue: Arc<Queue<usize>> = Default::default();
+        let mut reader = queue.reader();
+        for round in 0..ROUNDS {
+            let mut writer = queue.writer();
+            while writer.block.len.load(Ordering::Relaxed) < writer.block.capacity {
+                writer.push(0);
+            }
+            
+            // All writers start at the same full block.
+            let full_block = writer.block.clone();
+            let start = Arc::new(std::sync::Barrier::new(WRITERS));
+            let joins: Vec<_> = (0..WRITERS).map(|w| {
+                let mut writer = queue.writer();
+                let start = start.clone();
+                std::thread::spawn(move || {
+                    start.wait();
+                    writer.push(round * WRITERS + w + 1);
+                })
+            }).collect();
+            for join in joins {
+                join.join().unwrap();
+            }
+            
+            let len = full_block.len.load(Ordering::Relaxed);
+            assert!(len <= full_block.capacity + WRITERS);
+            
+            let mut values: Vec<usize> = std::iter::from_fn(|| reader.next().copied())
+                .filter(|&value| value != 0)
+                .collect();
+            values.sort();
+            assert_equal(values, round * WRITERS + 1..=round * WRITERS + WRITERS);
+        }
+    }
+    
+    #[test]
+    #[cfg(feature = "std")]
+    fn notify_test() {
+        let queue: Arc<Queue<usize>> = Default::default();
+        let mut reader = queue.reader();
+        let mut writer = queue.writer();
+        
+        const COUNT: usize = BLOCK_SIZE * 2 + 10;
+        let join = std::thread::spawn(move || {
+            let mut values = Vec::new();
+            while values.len() != COUNT {
+                match reader.next() {
+                    Some(value) => values.push(*value),
+                    None => reader.wait(),
+                }
+            }
+            values
+        });
         
-        const COUNT: usize = BLOCK_SIZE * 4; 
         for i in 0..COUNT {
-            queue.blocking_push(i);
-            //writer.push(i);    
+            if i % 100 == 0 {
+                writer.push_notify(i);
+            } else {
+                writer.push(i);
+            }
         }
+        writer.notify();
         
-        let mut vec = Vec::new();
+        assert_equal(join.join().unwrap(), 0..COUNT);
+    }
+    
+    #[test]
+    #[cfg(feature = "std")]
+    fn recv_all_until_closed_test() {
+        let queue: Arc<Queue<usize>> = Default::default();
+        let readers: Vec<_> = (0..2).map(|_| {
+            let mut reader = queue.reader();
+            std::thread::spawn(move || reader.recv_all_until_closed().collect::<Vec<_>>())
+        }).collect();
+        
+        const WRITERS: usize = 4;
+        const COUNT: usize = BLOCK_SIZE * 2 + 10;
+        let writers: Vec<_> = (0..WRITERS).map(|w| {
+            let mut writer = queue.writer();
+            std::thread::spawn(move || {
+                for i in 0..COUNT {
+                    writer.push(w*COUNT + i);
+                }
+            })
+        }).collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+        queue.close();
+        assert!(queue.is_closed());
+        
+        for reader in readers {
+            let mut values = reader.join().unwrap();
+            values.sort();
+            assert_equal(values, 0..WRITERS*COUNT);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn adaptive_blocks_test() {
+        let queue: Arc<Queue<usize>> = Arc::new(Queue::default().with_adaptive_blocks());
+        let mut reader = queue.reader();
+        
+        const COUNT: usize = BLOCK_SIZE * 3;
+        let mut writer = queue.writer();
+        for i in 0..COUNT {
+            if i % 2 == 0 {
+                writer.push(i);
+            } else {
+                queue.blocking_push(i);
+            }
+        }
+        assert_eq!(reader.catch_up_count(&queue), COUNT);
+        
+        let mut capacities = Vec::new();
+        reader.for_each_block(|info| capacities.push(info.capacity));
+        assert_eq!(capacities[0], crate::growth::MIN_ADAPTIVE_CAPACITY);
+        
+        let mut values = Vec::new();
         while let Some(value) = reader.next() {
-            vec.push(value.clone());
+            values.push(*value);
+        }
+        assert_equal(values, 0..COUNT);
+    }
+    
+    #[test]
+    fn len_test() {
+        let queue: Arc<Queue<usize>> = Default::default();
+        let mut reader = queue.reader();
+        let mut writer = queue.writer();
+        assert!(queue.is_empty());
+        for i in 0..BLOCK_SIZE+10 {
+            writer.push(i);
+        }
+        assert_eq!(queue.approx_len(), BLOCK_SIZE+10);
+        while reader.next().is_some() {}
+        assert!(queue.is_empty());
+    }
+    
+    #[test]
+    fn next_indexed_test() {
+        let queue: Arc<Queue<usize, 64>> = Default::default();
+        let mut reader = queue.reader();
+        let mut writer = queue.writer();
+        for i in 0..64*2 {
+            if i == 10 {
+                // Aborted slot takes index.
+                drop(writer.begin_push());
+            } else {
+                writer.push(i);
+            }
+        }
+        for i in (0..64*2).filter(|&i| i != 10) {
+            assert_eq!(reader.next_indexed(), Some((i as u64, &i)));
+        }
+        assert_eq!(reader.next_indexed(), None);
+    }
+    
+    #[test]
+    fn skip_to_tail_test() {
+        let queue: Arc<Queue<usize>> = Default::default();
+        let mut reader = queue.reader();
+        for i in 0..BLOCK_SIZE*2+10 {
+            queue.blocking_push(i);
+        }
+        assert_eq!(reader.next(), Some(&0));
+        reader.skip_to_tail();
+        assert_eq!(reader.lag(), 0);
+        assert_eq!(reader.next(), None);
+        
+        // At the block end.
+        for i in 0..BLOCK_SIZE-10 {
+            queue.blocking_push(i);
+        }
+        reader.skip_to_tail();
+        assert_eq!(reader.next(), None);
+        queue.blocking_push(1000);
+        assert_eq!(reader.next(), Some(&1000));
+        assert_eq!(reader.next(), None);
+    }
+    
+    #[test]
+    fn is_closed_and_drained_test() {
+        let queue: Arc<Queue<usize>> = Default::default();
+        let mut reader = queue.reader();
+        let writers: Vec<_> = (0..4).map(|_| {
+            let queue = queue.clone();
+            std::thread::spawn(move || {
+                for i in 0..BLOCK_SIZE {
+                    queue.blocking_push(i);
+                }
+            })
+        }).collect();
+        for writer in writers {
+            writer.join().unwrap();
+        }
+        assert!(!reader.is_closed_and_drained());
+        queue.close();
+        
+        let mut sum = 0;
+        while !reader.is_closed_and_drained() {
+            sum += reader.next().unwrap();
+        }
+        assert_eq!(sum, (0..BLOCK_SIZE).sum::<usize>() * 4);
+        assert_eq!(reader.next(), None);
+    }
+    
+    #[test]
+    fn writer_update_test() {
+        let value = Arc::new(0);
+        let queue: Arc<Queue<Arc<usize>, 64>> = Default::default();
+        let mut idle = queue.writer();
+        let mut busy = queue.writer();
+        idle.push(value.clone());
+        for _ in 0..64*3 {
+            busy.push(value.clone());
+        }
+        
+        // Idle writer keeps all blocks alive.
+        assert_eq!(Arc::strong_count(&value), 1 + 1 + 64*3);
+        idle.update();
+        // Only the last block is left.
+        assert_eq!(Arc::strong_count(&value), 1 + 1);
+        
+        idle.push(value.clone());
+        assert_eq!(Arc::strong_count(&value), 1 + 2);
+    }
+    
+    #[test]
+    fn writer_update_mt_test() {
+        const WRITERS: usize = 4;
+        let value = Arc::new(0);
+        let queue: Arc<Queue<Arc<usize>, 64>> = Default::default();
+        let mut idle = queue.writer();
+        idle.push(value.clone());
+        
+        // Walks the block chain, or falls back to the queue's last block.
+        for blocks in [2, crate::mpmc::Writer::<Arc<usize>, 64>::UPDATE_MAX_JUMPS * 4] {
+            let writers: Vec<_> = (0..WRITERS).map(|_| {
+                let mut writer = queue.writer();
+                let value = value.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..64*blocks/WRITERS {
+                        writer.push(value.clone());
+                    }
+                })
+            }).collect();
+            for writer in writers {
+                writer.join().unwrap();
+            }
+            assert!(Arc::strong_count(&value) > 64*blocks);
+            
+            idle.update();
+            // Only the last block is left.
+            assert!(Arc::strong_count(&value) <= 1 + 64);
+        }
+        
+        #[cfg(feature = "metrics")]
+        assert_eq!(queue.metrics().writer_update_fallbacks, 1);
+    }
+    
+    #[test]
+    fn handle_count_test() {
+        let queue: Arc<Queue<usize>> = Default::default();
+        assert_eq!(queue.reader_count(), 0);
+        assert_eq!(queue.writer_count(), 0);
+        
+        let reader0 = queue.reader();
+        let reader1 = reader0.clone();
+        let subscription = queue.subscribe();
+        assert_eq!(queue.reader_count(), 3);
+        
+        let writer0 = queue.writer();
+        let writer1 = std::thread::spawn({
+            let queue = queue.clone();
+            move || queue.writer()
+        }).join().unwrap();
+        assert_eq!(queue.writer_count(), 2);
+        
+        drop(reader0);
+        drop(subscription);
+        assert_eq!(queue.reader_count(), 1);
+        drop(writer0);
+        assert_eq!(queue.writer_count(), 1);
+        drop(reader1);
+        drop(writer1);
+        assert_eq!(queue.reader_count(), 0);
+        assert_eq!(queue.writer_count(), 0);
+    }
+    
+    #[test]
+    fn lag_test() {
+        let queue: Arc<Queue<usize>> = Default::default();
+        let mut reader = queue.reader();
+        let mut writer = queue.writer();
+        assert_eq!(reader.lag(), 0);
+        for i in 0..BLOCK_SIZE+10 {
+            writer.push(i);
+        }
+        reader.next();
+        assert_eq!(reader.lag(), BLOCK_SIZE+9);
+        
+        // Counts up to the first in-flight message.
+        let mut writer2 = queue.writer();
+        let slot = writer2.begin_push();
+        writer.push(0);
+        assert_eq!(reader.lag(), BLOCK_SIZE+9);
+        drop(slot);
+        assert_eq!(reader.lag(), BLOCK_SIZE+11);
+        
+        while reader.next().is_some() {}
+        assert_eq!(reader.lag(), 0);
+    }
+    
+    #[test]
+    fn block_size_test() {
+        let queue: Arc<Queue<usize, 64>> = Default::default();
+        let reader = queue.reader();
+        let mut writer = queue.writer();
+        for i in 0..200 {
+            writer.push(i);
+        }
+        let mut group: Vec<_> = (200..264).collect();
+        writer.push_group(&mut group);
+        
+        let mut capacities = Vec::new();
+        reader.for_each_block(|info| capacities.push(info.capacity));
+        assert!(capacities.iter().all(|&capacity| capacity == 64));
+        assert_equal(reader.cloned(), 0..264);
+    }
+
+    #[test]
+    fn quiesce_test() {
+        let queue: Arc<Queue<usize>> = Default::default();
+        let consumed = Arc::new(AtomicUsize::new(0));
+        let stop = Arc::new(AtomicBool::new(false));
+        
+        let mut joins = Vec::new();
+        for _ in 0..2 {
+            let mut reader = queue.reader();
+            let consumed = consumed.clone();
+            let stop = stop.clone();
+            joins.push(std::thread::spawn(move || {
+                while !stop.load(Ordering::Acquire) {
+                    if reader.next().is_some() {
+                        consumed.fetch_add(1, Ordering::Relaxed);
+                    }
+                }
+            }));
+        }
+        
+        const COUNT: usize = BLOCK_SIZE + 10;
+        let mut writer = queue.writer();
+        for i in 0..COUNT {
+            writer.push(i);
+        }
+        
+        // Dropped readers are not waited for.
+        let _ = queue.reader().clone();
+        
+        queue.quiesce();
+        assert_eq!(consumed.load(Ordering::Relaxed), COUNT*2);
+        
+        stop.store(true, Ordering::Release);
+        for join in joins{
+            join.join().unwrap();    
         }
-        assert_equal(vec, 0..COUNT);
     }
     
+    #[test]
+    fn quiesce_next_block_test() {
+        let queue: Arc<Queue<usize>> = Default::default();
+        let mut reader = queue.reader();
+        let mut writer = queue.writer();
+        for i in 0..BLOCK_SIZE*2 {
+            writer.push(i);
+        }
+        
+        std::thread::scope(|s| {
+            s.spawn(|| {
+                assert_equal(reader.next_block().unwrap().iter().copied(), 0..BLOCK_SIZE);
+                assert_equal(reader.next_block().unwrap().iter().copied(), BLOCK_SIZE..BLOCK_SIZE*2);
+            });
+            queue.quiesce();
+        });
+    }
+
     fn test_mpmc_mt<Value>(wt: usize, rt: usize, len: usize)
     where
-        Value: From<usize> + Into<usize> + Clone + 'static,
+        Value: From<usize> + Into<usize> + Clone + Send + 'static,
     {
         let queue: Arc<Queue<Value>> = Default::default();
 
         let mut joins = Vec::new();
 
         // Readers
-        let control_sum = (0..len).sum();        
+        let control_sum: usize = (0..len).sum();
         for _ in 0..rt { 
             let mut reader = queue.reader();
             joins.push(std::thread::spawn(move || {
@@ -0,0 +1,110 @@
+#[cfg(feature = "std")]
+use crate::sync::Condvar;
+use crate::sync::{fence, AtomicUsize, Ordering};
+#[cfg(feature = "async")]
+use core::task::Waker;
+#[cfg(feature = "async")]
+use alloc::vec::Vec;
+use crate::sync::Mutex;
+
+/// Wakes up readers, blocked waiting for messages. And, with `async` 
+/// feature - registered wakers.
+///
+/// # Lost wake-ups
+///
+/// Writer publishes message with any (Release) store, then [notify()] issues 
+/// SeqCst fence and loads `waiters`. Reader increments `waiters` with SeqCst, 
+/// then checks for message with SeqCst load. Fence and SeqCst operations are
+/// totally ordered - so either writer sees the waiter, or waiter sees 
+/// the message. Registered waker counts as waiter, until woken.
+/// 
+/// So publish stays cheap - only those who notify pay for the fence.
+/// 
+/// Without `std` feature, waiters spin instead of sleep.
+/// 
+/// [notify()]: Self::notify
+#[derive(Default)]
+pub(crate) struct Notifier {
+    waiters: AtomicUsize,
+    mutex  : Mutex<()>,
+    #[cfg(feature = "std")]
+    condvar: Condvar,
+    #[cfg(feature = "async")]
+    wakers : Mutex<Vec<Waker>>,
+}
+
+impl Notifier {
+    /// Wake all waiters, if any. Call after publishing.
+    /// 
+    /// Cheap if there are no waiters - but still a full memory fence.
+    #[inline]
+    pub fn notify(&self) {
+        // Order publish before `waiters` load.
+        fence(Ordering::SeqCst);
+        if self.waiters.load(Ordering::Relaxed) != 0 {
+            self.notify_all();
+        }
+    }
+
+    #[cold]
+    #[inline(never)]
+    fn notify_all(&self) {
+        // Take the lock, so we can't notify between waiter's
+        // check and its sleep.
+        {
+            let _guard = self.mutex.lock();
+            #[cfg(feature = "std")]
+            self.condvar.notify_all();
+        }
+        
+        #[cfg(feature = "async")]
+        {
+            let wakers = core::mem::take(
+                &mut *self.wakers.lock()
+            );
+            self.waiters.fetch_sub(wakers.len(), Ordering::SeqCst);
+            for waker in wakers {
+                waker.wake();
+            }
+        }
+    }
+    
+    /// Registers `waker` to be woken once, on the next notify.
+    /// 
+    /// After that, check messages with SeqCst loads - before going Pending.
+    #[cfg(feature = "async")]
+    pub fn register(&self, waker: &Waker) {
+        let mut wakers = self.wakers.lock();
+        if wakers.iter().any(|registered| registered.will_wake(waker)) {
+            return;
+        }
+        wakers.push(waker.clone());
+        self.waiters.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Blocks while `condition` is true.
+    ///
+    /// `condition` must check messages with SeqCst loads.
+    #[cold]
+    pub fn wait_while(&self, mut condition: impl FnMut() -> bool) {
+        self.waiters.fetch_add(1, Ordering::SeqCst);
+        #[cfg(feature = "std")]
+        {
+            let mut guard = self.mutex.lock();
+            while condition() {
+                guard = self.condvar.wait(guard).unwrap_or_else(|e| e.into_inner());
+            }
+        }
+        #[cfg(not(feature = "std"))]
+        while condition() {
+            core::hint::spin_loop();
+        }
+        self.waiters.fetch_sub(1, Ordering::SeqCst);
+    }
+    
+    /// Holds notifier lock - anyone who takes it blocks, while guard lives.
+    #[cfg(test)]
+    pub fn lock(&self) -> crate::sync::MutexGuard<'_, ()> {
+        self.mutex.lock()
+    }
+}
@@ -0,0 +1,50 @@
+use core::fmt;
+use core::ops::Deref;
+use crate::block::BlockArc;
+
+/// Message, that lives as long as the guard - not until the next read.
+///
+/// Keeps message's block alive. So holding guards for long keeps
+/// the rest of queue after it alive too - as a reader that stands there.
+///
+/// Constructed by `Reader::next_guard()`.
+pub struct ReadGuard<T> {
+    block: BlockArc<T>,
+    value: *const T,
+}
+
+impl<T> ReadGuard<T> {
+    /// `value` must point into `block`.
+    #[inline]
+    pub(crate) unsafe fn new(block: BlockArc<T>, value: *const T) -> Self {
+        Self{ block, value }
+    }
+}
+
+// Last guard may drop the block, with its messages.
+unsafe impl<T: Send + Sync> Send for ReadGuard<T> {}
+unsafe impl<T: Sync> Sync for ReadGuard<T> {}
+
+impl<T> Deref for ReadGuard<T> {
+    type Target = T;
+
+    #[inline]
+    fn deref(&self) -> &T {
+        // Messages are dropped only with the block.
+        unsafe{ &*self.value }
+    }
+}
+
+impl<T> Clone for ReadGuard<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self{ block: self.block.clone(), value: self.value }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for ReadGuard<T> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
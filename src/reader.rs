@@ -1,3 +1,14 @@
+use alloc::borrow::Cow;
+#[cfg(feature = "std")]
+use alloc::collections::VecDeque;
+#[cfg(feature = "std")]
+use std::collections::HashSet;
+use core::fmt::Debug;
+use core::hash::Hash;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use crate::sync::{Mutex, MutexGuard};
+
 // TODO: next_slice()
 /// Lending queue consumer trait.
 /// 
@@ -24,21 +35,107 @@
 /// reader is mutated. This means the value is guaranteed to live until the next 
 /// read operation, at which point the block may change, and the old block could 
 /// be destructed.
+/// 
+/// This contract is enforced by the borrow checker - holding a message across 
+/// the next read does not compile:
+/// ```compile_fail
+/// # let mut queue: chute::spmc::Queue<usize> = Default::default();
+/// # let mut reader = queue.reader();
+/// # use chute::LendingReader;
+/// # queue.push(1);
+/// # queue.push(2);
+/// let first = reader.next();
+/// let second = reader.next();
+/// println!("{first:?} {second:?}");
+/// ```
 pub trait LendingReader: Sized {
     type Item;
     
     fn next(&mut self) -> Option<&Self::Item>;
     
+    /// Same as [next()], but returns [Cow::Borrowed]. 
+    /// 
+    /// Call [into_owned()] on the messages you want to keep - only these are cloned.
+    /// 
+    /// ```
+    /// # use chute::LendingReader;
+    /// let mut queue = chute::spmc::Queue::new();
+    /// let mut reader = queue.reader();
+    /// queue.push(String::from("skip"));
+    /// queue.push(String::from("keep"));
+    /// 
+    /// let mut kept = Vec::new();
+    /// while let Some(value) = reader.next_cow() {
+    ///     if *value == "keep" {
+    ///         kept.push(value.into_owned());
+    ///     }
+    /// }
+    /// assert_eq!(kept, ["keep"]);
+    /// ```
+    /// 
+    /// [next()]: Self::next
+    /// [into_owned()]: Cow::into_owned
+    #[inline]
+    fn next_cow(&mut self) -> Option<Cow<'_, Self::Item>>
+    where
+        Self::Item: Clone
+    {
+        self.next().map(Cow::Borrowed)
+    }
+    
+    /// Skips messages until one matches `predicate`, and returns it.
+    /// 
+    /// Returns `None` if there are no more messages to read - all read
+    /// messages are consumed then.
+    /// 
+    /// ```
+    /// # use chute::LendingReader;
+    /// let mut queue = chute::spmc::Queue::new();
+    /// let mut reader = queue.reader();
+    /// for value in ["garbage", "SYNC", "data"] {
+    ///     queue.push(value);
+    /// }
+    /// assert_eq!(reader.next_until(|value| *value == "SYNC"), Some(&"SYNC"));
+    /// assert_eq!(reader.next(), Some(&"data"));
+    /// assert_eq!(reader.next_until(|value| *value == "SYNC"), None);
+    /// ```
+    #[inline]
+    fn next_until(&mut self, mut predicate: impl FnMut(&Self::Item) -> bool) -> Option<&Self::Item> {
+        loop {
+            // Pointer, because borrow checker can not see that a conditionally
+            // returned borrow ends otherwise.
+            let value: *const Self::Item = self.next()?;
+            // Safety: reader is not touched until return.
+            let value = unsafe{ &*value };
+            if predicate(value) {
+                return Some(value);
+            }
+        }
+    }
+    
     #[inline]
     fn cloned(self) -> ClonedReader<Self> {
         ClonedReader{reader: self}
     } 
 }
 
+/// Reader, that can look at the next message without consuming it.
+pub trait PeekingReader: LendingReader {
+    /// Returns the next message, without consuming it. 
+    /// [next()] returns the same message.
+    /// 
+    /// [next()]: LendingReader::next
+    fn peek(&mut self) -> Option<&Self::Item>;
+}
+
 /// Cloning queue consumer.
 /// 
 /// Reader that clones `T` upon return. Implements [Iterator].
 ///
+/// Message is consumed before it is cloned. If `T::clone()` panics, that 
+/// message is skipped - reader stays valid, and continues from the next one.
+/// `Reader::copying()` and [PeekingReader::peek()] do not lose the message. 
+///
 /// Constructed by [LendingReader::cloned()]. 
 pub struct ClonedReader<R: LendingReader>{
     reader: R   
@@ -53,4 +150,645 @@ where
     fn next(&mut self) -> Option<Self::Item> {
         self.reader.next().cloned()
     }
-}
\ No newline at end of file
+}
+
+impl<R> ClonedReader<R>
+where
+    R: LendingReader<Item: Clone>
+{
+    /// Iterator over ready messages, that stops at the first moment there
+    /// are no messages to read - and stays stopped, even if new messages come. 
+    /// 
+    /// For periodic drains: the next `take_available()` resumes from where
+    /// previous one stopped.
+    /// 
+    /// ```
+    /// # use chute::LendingReader;
+    /// let mut queue = chute::spmc::Queue::new();
+    /// let mut reader = queue.reader().cloned();
+    /// queue.push(1);
+    /// queue.push(2);
+    /// 
+    /// let mut drain = reader.take_available();
+    /// assert_eq!(drain.next(), Some(1));
+    /// assert_eq!(drain.next(), Some(2));
+    /// assert_eq!(drain.next(), None);
+    /// 
+    /// queue.push(3);
+    /// assert_eq!(drain.next(), None);
+    /// drop(drain);
+    /// assert!(reader.take_available().eq([3]));
+    /// ```
+    #[inline]
+    pub fn take_available(&mut self) -> impl Iterator<Item = R::Item> + '_ {
+        self.by_ref().fuse()
+    }
+}
+
+#[cfg(feature = "arrayvec")]
+impl<R> ClonedReader<R>
+where
+    R: LendingReader<Item: Clone>
+{
+    /// Clones messages into `out`, until it is full or there are no more 
+    /// messages to read. Returns number of messages added.
+    /// 
+    /// Stack-friendly batch read.
+    /// 
+    /// ```
+    /// # use chute::LendingReader;
+    /// # use arrayvec::ArrayVec;
+    /// let mut queue = chute::spmc::Queue::new();
+    /// let mut reader = queue.reader().cloned();
+    /// for i in 0..5 {
+    ///     queue.push(i);
+    /// }
+    /// let mut batch = ArrayVec::<usize, 4>::new();
+    /// assert_eq!(reader.read_into_arrayvec(&mut batch), 4);
+    /// assert_eq!(batch.as_slice(), [0, 1, 2, 3]);
+    /// 
+    /// batch.clear();
+    /// assert_eq!(reader.read_into_arrayvec(&mut batch), 1);
+    /// assert_eq!(batch.as_slice(), [4]);
+    /// ```
+    pub fn read_into_arrayvec<const N: usize>(&mut self, out: &mut arrayvec::ArrayVec<R::Item, N>) -> usize {
+        let mut count = 0;
+        while !out.is_full() {
+            let Some(value) = self.reader.next() else { break };
+            out.push(value.clone());
+            count += 1;
+        }
+        count
+    }
+}
+
+/// Cloning queue consumer, that does not keep consumed blocks alive.
+/// 
+/// Same as [ClonedReader], but after reading the last message of a block, 
+/// moves to the next block right away - instead of on the next read. 
+/// So the consumed block can be freed as soon as other readers pass it,
+/// even if this reader is not polled for a long time.
+/// 
+/// Unlike [ClonedReader], message is consumed only after successful clone.
+/// If `T::clone()` panics, reader stays at that message.
+/// 
+/// Constructed by `Reader::copying()`.
+pub struct CopyingReader<R>{
+    pub(crate) reader: R
+}
+
+impl<R> CopyingReader<R>{
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+/// Reader, that can go back to where it subscribed.
+/// 
+/// Keeps queue blocks alive from the subscription point - so memory 
+/// is not freed, while it lives. Use for retry-from-start scenarios, 
+/// with bounded message count.
+/// 
+/// Constructed by `Queue::reader_rewindable()`.
+pub struct RewindableReader<R>{
+    pub(crate) reader: R,
+    /// Reader at subscription point. Never read.
+    pub(crate) origin: R,
+}
+
+impl<R: Clone> RewindableReader<R>{
+    /// Moves reader back to where it subscribed, so it reads
+    /// everything again.
+    /// 
+    /// Always succeeds - blocks from the subscription point are pinned.
+    #[inline]
+    pub fn rewind_to_subscription(&mut self) {
+        self.reader = self.origin.clone();
+    }
+    
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R: LendingReader> LendingReader for RewindableReader<R>{
+    type Item = R::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<&Self::Item> {
+        self.reader.next()
+    }
+}
+
+/// Deterministic merge of several readers (lanes).
+/// 
+/// Takes messages from lanes in round-robin order: one from lane 0, 
+/// then one from lane 1, and so on. If the current lane has no message - 
+/// returns `None` and stays on it.
+/// 
+/// Give each writer its own queue, and merge their readers - the result
+/// depends only on what each writer pushed, not on how writers interleaved.
+/// Unlike a shared mpmc queue, where order between writers is up to the race.
+/// 
+/// Lane that stops receiving messages stalls the whole merge.
+/// 
+/// ```
+/// # use chute::{LendingReader, RoundRobinReader};
+/// let mut lane0 = chute::spmc::Queue::new();
+/// let mut lane1 = chute::spmc::Queue::new();
+/// let mut reader = RoundRobinReader::new(vec![lane0.reader(), lane1.reader()]);
+/// 
+/// lane1.push("b1");
+/// lane1.push("b2");
+/// lane0.push("a1");
+/// lane0.push("a2");
+/// 
+/// assert_eq!(reader.cloned().collect::<Vec<_>>(), ["a1", "b1", "a2", "b2"]);
+/// ```
+pub struct RoundRobinReader<R>{
+    lanes: Vec<R>,
+    lane: usize,
+}
+
+impl<R: LendingReader> RoundRobinReader<R>{
+    /// # Panics
+    /// 
+    /// Panics if `lanes` is empty.
+    #[inline]
+    pub fn new(lanes: Vec<R>) -> Self {
+        assert!(!lanes.is_empty(), "RoundRobinReader needs at least one lane.");
+        Self{ lanes, lane: 0 }
+    }
+    
+    /// Lane, the next message will be taken from.
+    #[inline]
+    pub fn current_lane(&self) -> usize {
+        self.lane
+    }
+    
+    #[inline]
+    pub fn into_inner(self) -> Vec<R> {
+        self.lanes
+    }
+}
+
+impl<R: LendingReader> LendingReader for RoundRobinReader<R>{
+    type Item = R::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<&Self::Item> {
+        let lane  = self.lane;
+        let lanes = self.lanes.len();
+        let value = self.lanes[lane].next()?;
+        self.lane = (lane + 1) % lanes;
+        Some(value)
+    }
+}
+
+/// Shared queue consumer.
+/// 
+/// Reader behind a mutex, that can be used through `&self`. Clones share 
+/// the same position - each message is returned by [recv()] only once, to one 
+/// of the callers. 
+/// 
+/// If each party needs to get every message, hand out independent readers
+/// with [fork()] instead.
+/// 
+/// ```
+/// # use chute::SyncReader;
+/// let mut queue = chute::spmc::Queue::new();
+/// let reader = SyncReader::new(queue.reader());
+/// let mut independent = reader.fork();
+/// queue.push(1);
+/// queue.push(2);
+/// 
+/// let shared = reader.clone();
+/// assert_eq!(reader.recv(), Some(1));
+/// assert_eq!(shared.recv(), Some(2));
+/// assert_eq!(reader.recv(), None);
+/// 
+/// # use chute::LendingReader;
+/// assert_eq!(independent.next(), Some(&1));
+/// ``` 
+/// 
+/// [recv()]: Self::recv
+/// [fork()]: Self::fork
+pub struct SyncReader<R>{
+    reader: Arc<Mutex<R>>
+}
+
+impl<R> Clone for SyncReader<R>{
+    #[inline]
+    fn clone(&self) -> Self {
+        Self{ reader: self.reader.clone() }
+    }
+}
+
+impl<R: LendingReader> SyncReader<R>{
+    #[inline]
+    pub fn new(reader: R) -> Self {
+        Self{ reader: Arc::new(Mutex::new(reader)) }
+    }
+    
+    #[inline]
+    fn lock(&self) -> MutexGuard<'_, R> {
+        // Reader state is always consistent, so poison is irrelevant.
+        self.reader.lock()
+    }
+    
+    /// Clone and return the next message.
+    #[inline]
+    pub fn recv(&self) -> Option<R::Item>
+    where
+        R::Item: Clone
+    {
+        self.lock().next().cloned()
+    }
+    
+    /// Independent reader, starting from the current shared position.
+    #[inline]
+    pub fn fork(&self) -> R
+    where
+        R: Clone
+    {
+        self.lock().clone()
+    }
+}
+
+/// Reader that asserts message order.
+/// 
+/// Panics if message key, extracted with `key`, is less than the previous one.
+/// Testing aid, for catching order guarantee violations.
+/// 
+/// ```
+/// # use chute::{LendingReader, OrderedReader};
+/// let mut queue = chute::spmc::Queue::new();
+/// let mut reader = OrderedReader::new(queue.reader(), |value: &(usize, &str)| value.0);
+/// queue.push((1, "a"));
+/// queue.push((1, "b"));
+/// queue.push((2, "c"));
+/// while let Some(_) = reader.next() {}
+/// ```
+/// 
+/// ```should_panic
+/// # use chute::{LendingReader, OrderedReader};
+/// let mut queue = chute::spmc::Queue::new();
+/// let mut reader = OrderedReader::new(queue.reader(), |value: &usize| *value);
+/// queue.push(2);
+/// queue.push(1);
+/// while let Some(_) = reader.next() {}
+/// ```
+pub struct OrderedReader<R, F, K>{
+    reader: R,
+    key: F,
+    last_key: Option<K>,
+}
+
+impl<R, F, K> OrderedReader<R, F, K>
+where
+    R: LendingReader,
+    F: FnMut(&R::Item) -> K,
+    K: PartialOrd + Debug
+{
+    #[inline]
+    pub fn new(reader: R, key: F) -> Self {
+        Self{ reader, key, last_key: None }
+    }
+    
+    /// Key of the last returned message.
+    #[inline]
+    pub fn last_key(&self) -> Option<&K> {
+        self.last_key.as_ref()
+    }
+    
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R, F, K> LendingReader for OrderedReader<R, F, K>
+where
+    R: LendingReader,
+    F: FnMut(&R::Item) -> K,
+    K: PartialOrd + Debug
+{
+    type Item = R::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<&Self::Item> {
+        let value = self.reader.next()?;
+        let key = (self.key)(value);
+        if let Some(last_key) = &self.last_key {
+            assert!(last_key <= &key, "Message out of order: {key:?} after {last_key:?}.");
+        }
+        self.last_key = Some(key);
+        Some(value)
+    }
+}
+
+/// Reader that stops at the first gap in message sequence.
+/// 
+/// Returns messages while their keys, extracted with `key`, are exactly 
+/// `start_key`, `start_key + 1`, and so on. At the first message with 
+/// unexpected key, returns `None` without consuming it - and keeps doing
+/// so.
+/// 
+/// ```
+/// # use chute::{LendingReader, SequentialReader};
+/// let mut queue = chute::spmc::Queue::new();
+/// let mut reader = SequentialReader::new(queue.reader(), 10, |value: &u64| *value);
+/// queue.push(10);
+/// queue.push(11);
+/// queue.push(13);
+/// assert_eq!(reader.next(), Some(&10));
+/// assert_eq!(reader.next(), Some(&11));
+/// assert_eq!(reader.next(), None);
+/// assert_eq!(reader.next_key(), 12);
+/// 
+/// // Gapped message is still there.
+/// assert_eq!(reader.into_inner().next(), Some(&13));
+/// ```
+pub struct SequentialReader<R, F>{
+    reader: R,
+    key: F,
+    next_key: u64,
+}
+
+impl<R, F> SequentialReader<R, F>
+where
+    R: PeekingReader,
+    F: FnMut(&R::Item) -> u64
+{
+    #[inline]
+    pub fn new(reader: R, start_key: u64, key: F) -> Self {
+        Self{ reader, key, next_key: start_key }
+    }
+    
+    /// Key of the message, expected next.
+    #[inline]
+    pub fn next_key(&self) -> u64 {
+        self.next_key
+    }
+    
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<R, F> LendingReader for SequentialReader<R, F>
+where
+    R: PeekingReader,
+    F: FnMut(&R::Item) -> u64
+{
+    type Item = R::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<&Self::Item> {
+        let key = (self.key)(self.reader.peek()?);
+        if key != self.next_key {
+            return None;
+        }
+        self.next_key += 1;
+        self.reader.next()
+    }
+}
+
+/// Reader that skips messages with recently seen keys.
+/// 
+/// For at-least-once sources, that may resend messages. Keys, extracted 
+/// with `key`, of the last `window` returned messages are remembered. 
+/// Message with a remembered key is skipped.
+/// 
+/// Window is FIFO: when full, the key remembered first is forgotten. 
+/// Skipped duplicates do not refresh their key. Memory is bounded 
+/// by `window` keys.
+/// 
+/// ```
+/// # use chute::{DedupKeyReader, LendingReader};
+/// let mut queue = chute::spmc::Queue::new();
+/// let mut reader = DedupKeyReader::new(queue.reader(), 2, |value: &(u32, &str)| value.0);
+/// queue.push((1, "a"));
+/// queue.push((2, "b"));
+/// queue.push((1, "a"));  // resend
+/// queue.push((3, "c"));
+/// queue.push((1, "a"));  // out of window
+/// assert_eq!(reader.next(), Some(&(1, "a")));
+/// assert_eq!(reader.next(), Some(&(2, "b")));
+/// assert_eq!(reader.next(), Some(&(3, "c")));
+/// assert_eq!(reader.next(), Some(&(1, "a")));
+/// assert_eq!(reader.next(), None);
+/// ```
+#[cfg(feature = "std")]
+pub struct DedupKeyReader<R, F, K>{
+    reader: R,
+    key: F,
+    window: usize,
+    seen: HashSet<K>,
+    /// `seen` keys, oldest first.
+    order: VecDeque<K>,
+}
+
+#[cfg(feature = "std")]
+impl<R, F, K> DedupKeyReader<R, F, K>
+where
+    R: LendingReader,
+    F: FnMut(&R::Item) -> K,
+    K: Clone + Eq + Hash
+{
+    /// Remembers keys of the last `window` messages. 
+    /// With zero `window` - nothing is skipped.
+    #[inline]
+    pub fn new(reader: R, window: usize, key: F) -> Self {
+        Self{
+            reader, 
+            key, 
+            window,
+            seen : HashSet::with_capacity(window),
+            order: VecDeque::with_capacity(window),
+        }
+    }
+    
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+#[cfg(feature = "std")]
+impl<R, F, K> LendingReader for DedupKeyReader<R, F, K>
+where
+    R: LendingReader,
+    F: FnMut(&R::Item) -> K,
+    K: Clone + Eq + Hash
+{
+    type Item = R::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<&Self::Item> {
+        let Self{ reader, key, window, seen, order } = self;
+        if *window == 0 {
+            return reader.next();
+        }
+        reader.next_until(|value| {
+            let key = key(value);
+            if seen.contains(&key) {
+                return false;
+            }
+            if order.len() == *window {
+                let oldest = order.pop_front().unwrap();
+                seen.remove(&oldest);
+            }
+            seen.insert(key.clone());
+            order.push_back(key);
+            true
+        })
+    }
+}
+
+/// Reader that folds all available messages into one accumulator.
+/// 
+/// Each [next_fold()] folds messages with `fold`, until reader runs out 
+/// of them - a drain cycle. Then returns a snapshot of the accumulator. 
+/// For aggregating bursts into a single update.
+/// 
+/// By default, accumulator carries over between cycles. 
+/// With [with_reset()] - starts each cycle from `init`.
+/// 
+/// ```
+/// # use chute::FoldReader;
+/// let mut queue = chute::spmc::Queue::new();
+/// let mut reader = FoldReader::new(queue.reader(), 0, |sum, value| *sum += value);
+/// queue.push(1);
+/// queue.push(2);
+/// assert_eq!(reader.next_fold(), Some(3));
+/// assert_eq!(reader.next_fold(), None);
+/// queue.push(3);
+/// assert_eq!(reader.next_fold(), Some(6));
+/// 
+/// let mut reader = reader.with_reset(true);
+/// queue.push(4);
+/// assert_eq!(reader.next_fold(), Some(10));
+/// queue.push(5);
+/// assert_eq!(reader.next_fold(), Some(5));
+/// ```
+/// 
+/// [next_fold()]: Self::next_fold
+/// [with_reset()]: Self::with_reset
+pub struct FoldReader<R, A, F>{
+    reader: R,
+    init: A,
+    accumulator: A,
+    fold: F,
+    reset: bool,
+}
+
+impl<R, A, F> FoldReader<R, A, F>
+where
+    R: LendingReader,
+    A: Clone,
+    F: FnMut(&mut A, &R::Item)
+{
+    #[inline]
+    pub fn new(reader: R, init: A, fold: F) -> Self {
+        Self{
+            reader,
+            accumulator: init.clone(),
+            init,
+            fold,
+            reset: false,
+        }
+    }
+    
+    /// If `reset` - accumulator starts each drain cycle from `init`. 
+    /// Otherwise - carries over. 
+    #[inline]
+    pub fn with_reset(mut self, reset: bool) -> Self {
+        self.reset = reset;
+        self
+    }
+    
+    /// Folds all available messages, and returns the accumulator.
+    /// `None` if there were no messages. 
+    pub fn next_fold(&mut self) -> Option<A> {
+        let Self{ reader, accumulator, fold, .. } = self;
+        fold(accumulator, reader.next()?);
+        while let Some(value) = reader.next() {
+            fold(accumulator, value);
+        }
+        Some(if self.reset {
+            core::mem::replace(&mut self.accumulator, self.init.clone())
+        } else {
+            self.accumulator.clone()
+        })
+    }
+    
+    /// Current accumulator value.
+    #[inline]
+    pub fn accumulator(&self) -> &A {
+        &self.accumulator
+    }
+    
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+/// Reader position in queue - number of messages before it.
+/// 
+/// Plain number, that does not keep queue blocks alive. Serializes
+/// to bytes, to checkpoint reader progress externally. Restore with 
+/// `Reader::seek()`, from a reader that is still behind the offset.
+/// 
+/// ```
+/// # use chute::{LendingReader, ReaderOffset};
+/// let mut queue = chute::spmc::Queue::new();
+/// let mut reader = queue.reader();
+/// // Keeps history, for restore.
+/// let mut retention = reader.clone();
+/// for i in 0..10 {
+///     queue.push(i);
+/// }
+/// reader.next();
+/// reader.next();
+/// let bytes = reader.offset().to_bytes();
+/// 
+/// retention.seek(ReaderOffset::from_bytes(bytes)).unwrap();
+/// assert_eq!(retention.next(), Some(&2));
+/// ```
+/// 
+/// Offsets of readers of the same queue are ordered as readers are - 
+/// greater offset is ahead. Comparing offsets of different queues is 
+/// meaningless.
+/// 
+/// ```
+/// # use chute::LendingReader;
+/// let queue = chute::mpmc::Queue::new();
+/// let mut fast = queue.reader();
+/// let slow = queue.reader();
+/// queue.blocking_push(1);
+/// assert_eq!(fast.offset(), slow.offset());
+/// 
+/// fast.next();
+/// assert!(fast.offset() > slow.offset());
+/// ```
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub struct ReaderOffset(pub u64);
+
+impl ReaderOffset {
+    /// Little-endian.
+    #[inline]
+    pub fn to_bytes(self) -> [u8; 8] {
+        self.0.to_le_bytes()
+    }
+    
+    /// Little-endian.
+    #[inline]
+    pub fn from_bytes(bytes: [u8; 8]) -> Self {
+        Self(u64::from_le_bytes(bytes))
+    }
+}
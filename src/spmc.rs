@@ -4,21 +4,65 @@
 //! 
 //! Wrapping it in `Arc<Mutex>` will make it multi-producer. 
 
-use std::sync::atomic::Ordering;
-use std::ops::Deref;
+use core::{cmp, slice};
+use alloc::collections::VecDeque;
+use alloc::alloc::GlobalAlloc;
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use crate::sync::{fence, Ordering};
+use core::pin::Pin;
 use branch_hints::unlikely;
-use crate::block::{Block, BlockArc, BLOCK_SIZE};
-use crate::LendingReader;
+use crate::block::{Block, BlockAllocator, BlockArc, BlockDrain, BlockInfo, BLOCK_SIZE};
+use crate::growth::BlockCapacity;
+use crate::subscriptions::{ReaderState, Subscriptions};
+use crate::{CopyingReader, Error, LendingReader, PeekingReader, ReadGuard, ReaderLatch, ReaderOffset, RewindableReader, Subscription};
 
-pub struct Queue<T>{
-    last_block: BlockArc<T>
+/// `N` - block size. Multiple of 64. Smaller blocks - for large `T`, 
+/// or queues that are expected to stay small.
+/// 
+/// ```
+/// let mut queue: chute::spmc::Queue<[u8; 512], 64> = Default::default();
+/// queue.push([0; 512]);
+/// ```
+/// 
+/// ```compile_fail
+/// let queue: chute::spmc::Queue<u8, 100> = Default::default();
+/// ```
+pub struct Queue<T, const N: usize = BLOCK_SIZE>{
+    last_block: BlockArc<T>,
+    subscriptions: Arc<Subscriptions>,
+    block_meta: u64,
+    block_capacity: BlockCapacity,
+    on_first_overflow: Option<Box<dyn FnOnce() + Send + Sync>>,
+    /// See [Queue::with_rollover_threshold()].
+    rollover_threshold: Option<f64>,
+    /// `last_block` len, at which `next_block` is prepared.
+    rollover_len: usize,
+    /// Prepared block to follow `last_block`. Not linked.
+    next_block: Option<BlockArc<T>>,
+    /// See [Queue::with_max_blocks()].
+    max_blocks: usize,
+    /// Starts of the last `max_blocks` blocks. Empty, if unbounded.
+    block_starts: VecDeque<usize>,
 }
 
-impl<T> Default for Queue<T>{
+impl<T, const N: usize> Default for Queue<T, N>{
     #[inline]
     fn default() -> Self {
+        const { Block::<T>::check_capacity(N) };
+        let block_capacity = BlockCapacity::new(N);
         Self{
-            last_block: Block::new(),
+            last_block: Block::new(block_capacity.first()),
+            subscriptions: Default::default(),
+            block_meta: 0,
+            block_capacity,
+            on_first_overflow: None,
+            rollover_threshold: None,
+            rollover_len: usize::MAX,
+            next_block: None,
+            max_blocks: usize::MAX,
+            block_starts: VecDeque::new(),
         }
     }
 }
@@ -28,51 +72,662 @@ impl<T> Queue<T> {
     pub fn new() -> Self{
         Default::default()
     }
+}
+
+/// Pushes all values through [Queue::writer()] - wakes blocked readers once.
+/// 
+/// ```
+/// # use chute::LendingReader;
+/// let mut queue = chute::spmc::Queue::new();
+/// let mut reader = queue.reader();
+/// queue.extend((0..5).map(|i| i * 10));
+/// assert_eq!(reader.next(), Some(&0));
+/// assert_eq!(reader.next(), Some(&10));
+/// ```
+impl<T, const N: usize> Extend<T> for Queue<T, N> {
+    #[inline]
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let mut writer = self.writer();
+        for value in iter {
+            writer.push(value);
+        }
+    }
+}
+
+impl<T, const N: usize> Queue<T, N> {    
+    /// Overwrite freed block memory with zeros, after dropping messages.
+    /// 
+    /// Use it for sensitive data, that should not linger in freed heap.
+    /// Off by default.
+    #[must_use]
+    #[inline]
+    pub fn with_zeroize(self, zeroize: bool) -> Self {
+        self.last_block.zeroize.store(zeroize, Ordering::Relaxed);
+        self
+    }
+    
+    /// Touch memory of each new block on allocation, so the first writes 
+    /// to the block do not page-fault. Removes page-fault jitter from 
+    /// latency-critical writer, at the cost of allocation time.
+    /// Off by default.
+    #[must_use]
+    #[inline]
+    pub fn with_prefault(self, prefault: bool) -> Self {
+        self.last_block.set_prefault(prefault);
+        self
+    }
+    
+    /// Adapt block capacity to message rate.
+    /// 
+    /// Queue starts with small blocks, and grows them towards default 
+    /// block size as throughput increases - fewer allocations for busy queue.
+    /// If messages come rarely - block size shrinks back, keeping idle queue
+    /// memory-light.
+    #[cfg(feature = "std")]
+    #[must_use]
+    #[inline]
+    pub fn with_adaptive_blocks(mut self) -> Self {
+        self.block_capacity.set_adaptive();
+        
+        // Replace first block with a small one, if untouched.
+        let allocator = self.last_block.allocator.clone();
+        self.replace_first_block(self.block_capacity.first(), allocator);
+        self
+    }
+    
+    /// Allocate and free block memory with `allocator`, instead of the global
+    /// one. For example, to place blocks on a specific NUMA node.
+    /// 
+    /// Should be called on a fresh queue - blocks are allocated in advance. 
+    /// 
+    /// # Panics
+    /// 
+    /// Panics if queue already has messages or readers.
+    /// 
+    /// ```
+    /// # use std::alloc::System;
+    /// # use std::sync::Arc;
+    /// let queue = chute::spmc::Queue::<usize>::new()
+    ///     .with_block_allocator(Arc::new(System));
+    /// ```
+    #[must_use]
+    #[inline]
+    pub fn with_block_allocator(mut self, allocator: Arc<dyn GlobalAlloc + Send + Sync>) -> Self {
+        let capacity = self.last_block.capacity;
+        assert!(
+            self.replace_first_block(capacity, Some(allocator)), 
+            "Queue already has messages or readers."
+        );
+        self
+    }
+    
+    /// Same as [with_block_allocator()], but with [Allocator] - for example, 
+    /// an arena. Blocks keep the allocator, so it lives while readers hold 
+    /// blocks - even after the queue is dropped.
+    /// 
+    /// Requires nightly and `allocator_api` feature.
+    /// 
+    /// ```
+    /// #![feature(allocator_api)]
+    /// let queue = chute::spmc::Queue::<usize>::new()
+    ///     .with_allocator(std::alloc::System);
+    /// ```
+    /// 
+    /// [with_block_allocator()]: Self::with_block_allocator
+    /// [Allocator]: core::alloc::Allocator
+    #[cfg(feature = "allocator_api")]
+    #[must_use]
+    #[inline]
+    pub fn with_allocator<A>(self, allocator: A) -> Self
+    where
+        A: core::alloc::Allocator + Send + Sync + 'static
+    {
+        self.with_block_allocator(Arc::new(crate::block::AllocatorAdapter(allocator)))
+    }
+    
+    /// Prepare the next block in advance, when the current one is `threshold` 
+    /// full. So that [push()], that fills the block, does not allocate.
+    /// 
+    /// Smooths out latency spikes at block boundaries, at the cost of one 
+    /// extra block of memory. `threshold` is a fraction of block capacity,
+    /// in `0.0..1.0`. Off by default.
+    /// 
+    /// # Panics
+    /// 
+    /// Panics if `threshold` is out of range.
+    /// 
+    /// [push()]: Self::push
+    #[must_use]
+    #[inline]
+    pub fn with_rollover_threshold(mut self, threshold: f64) -> Self {
+        assert!((0.0..1.0).contains(&threshold), "Rollover threshold should be in 0.0..1.0.");
+        self.rollover_threshold = Some(threshold);
+        self.update_rollover_len();
+        self
+    }
+    
+    /// Keep at most `max_blocks` blocks reachable by readers. 
+    /// 
+    /// Readers that fall behind further, skip the oldest blocks - 
+    /// messages there are lost for them. See [Reader::skipped()].
+    /// 
+    /// Reader notices that at its next block switch, so a reader that is 
+    /// not read from keeps its blocks alive, as usual. The writer never
+    /// frees block in use - this bounds memory of slow, not stalled readers.
+    /// 
+    /// # Panics
+    /// 
+    /// Panics if `max_blocks` is 0.
+    /// 
+    /// ```
+    /// # use chute::LendingReader;
+    /// let mut queue = chute::spmc::Queue::<usize, 64>::default()
+    ///     .with_max_blocks(2);
+    /// let mut reader = queue.reader();
+    /// for i in 0..64*4 {
+    ///     queue.push(i);
+    /// }
+    /// // Reader already is in block 0 - it is read to the end.
+    /// for i in 0..64 {
+    ///     assert_eq!(reader.next(), Some(&i));
+    /// }
+    /// // Block 1 is dropped from the queue - continue from block 2.
+    /// assert_eq!(reader.next(), Some(&128));
+    /// assert_eq!(reader.skipped(), 64);
+    /// ```
+    #[must_use]
+    #[inline]
+    pub fn with_max_blocks(mut self, max_blocks: usize) -> Self {
+        assert!(max_blocks > 0, "max_blocks should be at least 1.");
+        self.max_blocks = max_blocks;
+        self.block_starts.clear();
+        self.block_starts.push_back(self.last_block.start);
+        self
+    }
+    
+    #[inline]
+    fn update_rollover_len(&mut self) {
+        self.rollover_len = match self.rollover_threshold {
+            Some(threshold) => cmp::max((self.last_block.capacity as f64 * threshold) as usize, 1),
+            None => usize::MAX,
+        };
+    }
+    
+    #[cold]
+    #[inline(never)]
+    fn prepare_next_block(&mut self) {
+        //  +1 counter for EventQueue::next_block
+        let capacity = self.block_capacity.next(self.last_block.capacity);
+        self.next_block = Some(self.last_block.new_next(1, capacity));
+    }
+    
+    /// Replaces the first block with a new one, if untouched. 
+    /// Returns `false` if block is in use.
+    fn replace_first_block(&mut self, capacity: usize, allocator: Option<BlockAllocator>) -> bool {
+        let last_block = &self.last_block;
+        if last_block.seq == 0 && last_block.is_unique() && last_block.len.load(Ordering::Relaxed) == 0 {
+            self.last_block = last_block.new_first(capacity, allocator);
+            self.update_rollover_len();
+            true
+        } else {
+            false
+        }
+    }
     
     #[cold]
     #[inline(never)]
     fn insert_block(&mut self) {
-        // 1. Make new block
+        // 1. Make new block, or take prepared one
         //    +1 counter for EventQueue::last_block
         //    +1 counter for Block::next
-        let mut new_block = Block::with_counter(2);
+        let mut new_block = match self.next_block.take() {
+            Some(mut new_block) => {
+                unsafe{ Block::inc_use_count(new_block.as_non_null()); }
+                new_block
+            }
+            None => {
+                let capacity = self.block_capacity.next(self.last_block.capacity);
+                self.last_block.new_next(2, capacity)
+            }
+        };
+        new_block.meta.store(self.block_meta, Ordering::Relaxed);
         
         // 2. Connect new block with old
         self.last_block.next.store(new_block.as_non_null().as_ptr(), Ordering::Release);
         
         // 3. Set new block
         self.last_block = new_block;
+        self.update_rollover_len();
+        
+        if self.max_blocks != usize::MAX {
+            self.block_starts.push_back(self.last_block.start);
+            if self.block_starts.len() > self.max_blocks {
+                self.block_starts.pop_front();
+                // After linking - so readers can reach it.
+                self.subscriptions.set_oldest_position(self.block_starts[0]);
+            }
+        }
+        
+        if self.last_block.seq == 1 {
+            if let Some(f) = self.on_first_overflow.take() {
+                f();
+            }
+        }
+    }
+    
+    /// Push value, and wake readers blocked in [Reader::wait()].
+    /// 
+    /// Same as [push()] followed by [notify()] - pays a full memory fence.
+    /// 
+    /// [push()]: Self::push
+    /// [notify()]: Self::notify
+    #[inline]
+    pub fn push_notify(&mut self, value: T) {
+        self.push(value);
+        self.notify();
     }
     
+    /// Push value. Does not wake readers blocked in [Reader::wait()].
+    /// 
+    /// Readers that are not blocked see the value as usual. 
+    /// Use [push_notify()], or call [notify()] after a batch of pushes, 
+    /// to wake blocked readers.
+    /// 
+    /// If panics - in block allocation, or in [on_first_overflow()] 
+    /// callback - `value` is dropped unpublished, and queue stays usable. 
+    /// Queue state is updated before `value` is written, and `value` is
+    /// published only after the write.
+    /// 
+    /// [push_notify()]: Self::push_notify
+    /// [notify()]: Self::notify
+    /// [on_first_overflow()]: Self::on_first_overflow
     #[inline]
     pub fn push(&mut self, value: T) {
         let mut len = self.last_block.len.load(Ordering::Relaxed);
-        if unlikely(len == BLOCK_SIZE) {
+        if unlikely(len == self.last_block.capacity) {
             self.insert_block();
             len = 0;
+        } else if unlikely(len == self.rollover_len) {
+            self.prepare_next_block();
         }
         
-        // Take & instead of &mut to make MIRI happy about shared access.
-        // Thou, we write with Unique access.
-        let last_block = self.last_block.deref();
+        // Write through the allocation pointer, to make MIRI happy about 
+        // shared access. Thou, we write with Unique access.
         unsafe{
-            let mem = last_block.mem().cast_mut();
+            let mem = self.last_block.mem();
             mem.add(len).write(value);
         }
         
-        last_block.len.store(len+1, Ordering::Release);
+        self.last_block.publish_len(len+1);
+    }
+    
+    /// Push copies of `values`, and wake readers blocked in [Reader::wait()] 
+    /// once - like mpmc group pushes.
+    /// 
+    /// Same as [push()] in a loop, but copies block by block - with one 
+    /// `len` publish per block, instead of one per value.
+    /// 
+    /// [push()]: Self::push
+    pub fn push_slice(&mut self, mut values: &[T])
+    where
+        T: Copy
+    {
+        if values.is_empty() {
+            return;
+        }
+        loop {
+            let mut len = self.last_block.len.load(Ordering::Relaxed);
+            if unlikely(len == self.last_block.capacity) {
+                self.insert_block();
+                len = 0;
+            }
+            let n = cmp::min(values.len(), self.last_block.capacity - len);
+            if unlikely((len..len+n).contains(&self.rollover_len)) {
+                self.prepare_next_block();
+            }
+            
+            unsafe{
+                self.last_block.write(len, values.as_ptr(), n);
+            }
+            self.last_block.publish_len(len+n);
+            
+            values = &values[n..];
+            if values.is_empty() {
+                break;
+            }
+        }
+        self.notify();
+    }
+    
+    /// Wake readers blocked in [Reader::wait()].
+    /// 
+    /// Cheap if there are no blocked readers - but still a full memory fence.
+    #[inline]
+    pub fn notify(&self) {
+        self.subscriptions.message_notifier.notify();
+    }
+    
+    /// Detaches the last block, if it is full, and no reader can reach it.
+    /// Its messages are moved out by value - for handing them over to 
+    /// another pipeline stage as a whole, without pushing one by one.
+    /// 
+    /// Queue continues in a new block. Returns `None`, if the last block
+    /// is not full, or there are readers on it or before it.
+    /// 
+    /// Requires `T: Unpin` - moving out would break [pinning] guarantee.
+    /// 
+    /// [pinning]: crate#pinning
+    pub fn take_full_block(&mut self) -> Option<OwnedBlock<T>>
+    where
+        T: Unpin
+    {
+        let block = &self.last_block;
+        if block.len.load(Ordering::Relaxed) != block.capacity || !block.is_unique() {
+            return None;
+        }
+        let block = self.last_block.clone();
+        self.insert_block();
+        // Queue released it - we own it alone now.
+        let len = block.capacity;
+        Some(OwnedBlock{ drain: BlockDrain::new(block, 0), len })
+    }
+    
+    /// Set metadata for blocks created from now on. 
+    /// 
+    /// Block metadata is immutable, and can be used by readers to skip
+    /// whole blocks. See [Reader::filter_blocks()].
+    #[inline]
+    pub fn set_block_meta(&mut self, meta: u64) {
+        self.block_meta = meta;
+    }
+    
+    #[inline]
+    pub fn block_meta(&self) -> u64 {
+        self.block_meta
+    }
+    
+    /// Replaces the whole queue with `new`, returning the old one.
+    ///
+    /// Useful for double-buffering: build a new queue off to the side, then
+    /// flip it in.
+    ///
+    /// Existing readers are not affected - they keep reading the old queue's
+    /// blocks to its end, and never see messages pushed to `new`.
+    /// Readers constructed after this call read from `new`, starting from its
+    /// current end (same as [reader()]).
+    ///
+    /// Old blocks are freed when both returned queue and all its readers are dropped.
+    ///
+    /// [reader()]: Self::reader
+    #[must_use]
+    #[inline]
+    pub fn swap(&mut self, new: Self) -> Self {
+        core::mem::replace(self, new)
+    }
+
+    /// Returns latch, that will be released after `n` more readers
+    /// are constructed with [reader()].
+    ///
+    /// [reader()]: Self::reader
+    #[must_use]
+    #[inline]
+    pub fn barrier(&self, n: usize) -> ReaderLatch {
+        ReaderLatch::new(self.subscriptions.clone(), n)
+    }
+
+    /// Blocks until every reader consumes all messages pushed before this call.
+    ///
+    /// Readers report their progress when they run out of messages - that is,
+    /// when [next()] returns `None`. So this will block forever, if some reader
+    /// is not polled.
+    ///
+    /// Readers dropped during the wait are not waited for. Readers constructed
+    /// during the wait start past the messages in question, so they do not
+    /// delay it.
+    ///
+    /// [next()]: LendingReader::next
+    #[inline]
+    pub fn quiesce(&self) {
+        self.subscriptions.wait_readers(self.write_position());
+    }
+    
+    /// Release memory fence. See [crate#memory-ordering].
+    #[inline]
+    pub fn release_fence(&self) {
+        fence(Ordering::Release);
+    }
+    
+    /// Is queue still in its first block? 
+    /// 
+    /// `false` means queue has overflowed its first block at least once.
+    #[inline]
+    pub fn is_single_block(&self) -> bool {
+        self.last_block.seq == 0
+    }
+    
+    /// Sets `f` to be called once, when the queue overflows its first block -
+    /// that is, when the second block is allocated. 
+    /// 
+    /// A canary for queues, expected to stay small. Never called, if queue
+    /// has already overflowed. Replaces previously set callback.
+    /// 
+    /// Called from within [push()].
+    /// 
+    /// [push()]: Self::push
+    #[inline]
+    pub fn on_first_overflow(&mut self, f: impl FnOnce() + Send + Sync + 'static) {
+        self.on_first_overflow = Some(Box::new(f));
+    }
+    
+    /// Position of the next pushed message.
+    #[inline]
+    fn write_position(&self) -> usize {
+        self.last_block.start + self.last_block.len.load(Ordering::Relaxed)
+    }
+
+    /// Same as [reader()], but wrapped in [Subscription] - 
+    /// counted in [subscriber_count()] until dropped.
+    /// 
+    /// [reader()]: Self::reader
+    /// [subscriber_count()]: Self::subscriber_count
+    #[must_use]
+    #[inline]
+    pub fn subscribe(&self) -> Subscription<Reader<T>> {
+        let reader = self.reader();
+        let id = self.subscriptions.add_subscriber(&reader.state);
+        Subscription::new(reader, id, self.subscriptions.clone())
+    }
+    
+    /// Marks queue closed - no more messages expected - and wakes 
+    /// blocked readers.
+    /// 
+    /// Pushing after close is a logic error. Queue does not prevent it, 
+    /// but such messages may be missed by [Reader::try_next()] and 
+    /// [Reader::is_closed_and_drained()].
+    /// 
+    /// Dropped queue is closed as well.
+    #[inline]
+    pub fn close(&self) {
+        self.subscriptions.close();
+    }
+    
+    #[inline]
+    pub fn is_closed(&self) -> bool {
+        self.subscriptions.is_closed()
+    }
+    
+    /// Number of live [Subscription]s.
+    #[inline]
+    pub fn subscriber_count(&self) -> usize {
+        self.subscriptions.subscriber_count()
+    }
+    
+    /// Lag of each live [Subscription] - number of messages between its 
+    /// reader and the queue end. By [Subscription::id()], in ascending order.
+    /// 
+    /// Reader reports its position, when it runs out of messages, and on 
+    /// each block switch. So reader busy with a backlog, shows lag since its 
+    /// last report - up to a block more, than the actual one.
+    pub fn readers_lag(&self) -> Vec<(u64, usize)> {
+        let write_position = self.write_position();
+        let mut positions = self.subscriptions.subscriber_positions();
+        for (_, position) in &mut positions {
+            *position = write_position.saturating_sub(*position);
+        }
+        positions
+    }
+    
+    /// Estimate of messages, that the slowest reader has not read yet. 
+    /// 0 if there are no readers.
+    /// 
+    /// Reader reports its position, when it runs out of messages, and on 
+    /// each block switch. So this is an upper bound, stale by less than 
+    /// a block - reader busy with a backlog, counts from its last report.
+    #[inline]
+    pub fn approx_len(&self) -> usize {
+        self.write_position().saturating_sub(self.subscriptions.min_position())
+    }
+    
+    /// [approx_len()] is 0 - every reader reported, that it read 
+    /// everything pushed.
+    /// 
+    /// [approx_len()]: Self::approx_len
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.approx_len() == 0
+    }
+    
+    /// Same as [reader()], but reader can [rewind] to this point later.
+    /// 
+    /// [reader()]: Self::reader
+    /// [rewind]: RewindableReader::rewind_to_subscription
+    #[must_use]
+    #[inline]
+    pub fn reader_rewindable(&self) -> RewindableReader<Reader<T>> {
+        let origin = self.reader();
+        // Origin is not a consumer - it should not hold back quiesce().
+        origin.state.report(usize::MAX);
+        RewindableReader{
+            reader: origin.clone(),
+            origin,
+        }
+    }
+    
+    /// Writer, that keeps block length locally - instead of loading it
+    /// on each push. For tight push loops.
+    /// 
+    /// ```
+    /// # use chute::LendingReader;
+    /// let mut queue = chute::spmc::Queue::new();
+    /// let mut reader = queue.reader();
+    /// let mut writer = queue.writer();
+    /// for i in 0..3 {
+    ///     writer.push(i);
+    /// }
+    /// drop(writer);
+    /// assert_eq!(reader.next_chunk(), Some(&[0, 1, 2][..]));
+    /// ```
+    #[must_use]
+    #[inline]
+    pub fn writer(&mut self) -> Writer<'_, T, N> {
+        let len = self.last_block.len.load(Ordering::Relaxed);
+        Writer{ queue: self, len }
     }
     
+    /// [Reader] will receive all messages that are pushed AFTER this call.
     #[must_use]
     #[inline]
     pub fn reader(&self) -> Reader<T> {
         let last_block = self.last_block.clone();
-        let block_len  = last_block.len.load(Ordering::Acquire);
+        let block_len  = last_block.load_len();
+        let state = self.subscriptions.subscribe(last_block.start + block_len);
         Reader {
             block: last_block,
             index: block_len,
             len:   block_len,
+            state,
+            on_block_advance: None,
+            skipped: 0,
+        }
+    }
+    
+    /// Queue with `values` pushed, and the reader before them.
+    /// 
+    /// Counterpart of [Reader::snapshot()].
+    /// 
+    /// ```
+    /// # use chute::LendingReader;
+    /// let mut queue = chute::spmc::Queue::new();
+    /// let mut reader = queue.reader();
+    /// queue.push(1);
+    /// queue.push(2);
+    /// reader.next();
+    /// let snapshot = reader.snapshot();
+    /// 
+    /// let (_queue, mut reader) = chute::spmc::Queue::<_>::from_snapshot(snapshot);
+    /// assert_eq!(reader.next(), Some(&2));
+    /// assert_eq!(reader.next(), None);
+    /// ```
+    pub fn from_snapshot(values: impl IntoIterator<Item = T>) -> (Self, Reader<T>) {
+        let mut queue = Self::default();
+        let reader = queue.reader();
+        queue.extend(values);
+        (queue, reader)
+    }
+}
+
+/// Queue producer, that borrows the queue.
+/// 
+/// Pushes do not wake readers blocked in [Reader::wait()] - writer does 
+/// that on drop, or with [notify()].
+/// 
+/// Constructed by [Queue::writer()].
+/// 
+/// [notify()]: Self::notify
+pub struct Writer<'a, T, const N: usize = BLOCK_SIZE> {
+    queue: &'a mut Queue<T, N>,
+    /// `queue.last_block` len. Nobody else can push while writer lives.
+    len: usize,
+}
+
+impl<T, const N: usize> Writer<'_, T, N> {
+    /// Same as [Queue::push()].
+    #[inline]
+    pub fn push(&mut self, value: T) {
+        let queue = &mut *self.queue;
+        let len = self.len;
+        if unlikely(len == queue.last_block.capacity || len == queue.rollover_len) {
+            self.push_slow(value);
+            return;
         }
+        unsafe{
+            queue.last_block.mem().add(len).write(value);
+        }
+        queue.last_block.publish_len(len+1);
+        self.len = len + 1;
+    }
+    
+    /// Push through queue, and reload `len`. 
+    /// 
+    /// If push panics, `len` is left as is - and next push comes here again.
+    #[cold]
+    #[inline(never)]
+    fn push_slow(&mut self, value: T) {
+        self.queue.push(value);
+        self.len = self.queue.last_block.len.load(Ordering::Relaxed);
+    }
+    
+    /// Wake readers blocked in [Reader::wait()].
+    #[inline]
+    pub fn notify(&self) {
+        self.queue.notify();
+    }
+}
+
+impl<T, const N: usize> Drop for Writer<'_, T, N> {
+    #[inline]
+    fn drop(&mut self) {
+        self.queue.notify();
     }
 }
 
@@ -83,71 +738,2015 @@ pub struct Reader<T>{
     pub(crate) block: BlockArc<T>,
     pub(crate) index: usize,
     pub(crate) len  : usize,
+    pub(crate) state: ReaderState,
+    /// Called with block sequence number on each block switch.
+    pub(crate) on_block_advance: Option<Box<dyn FnMut(u64) + Send + Sync>>,
+    /// See [Reader::skipped()].
+    pub(crate) skipped: usize,
 }
 
-impl<T> Clone for Reader<T> {
+impl<T> Reader<T> {
+    /// Position in queue.
     #[inline]
-    fn clone(&self) -> Self {
-        Self{
-            block: self.block.clone(),
-            index: self.index,
-            len  : self.len,
-        }
+    fn position(&self) -> usize {
+        self.block.start + self.index
     }
-}
-
-impl<T> LendingReader for Reader<T>{
-    type Item = T;
-
+    
+    /// Acquire memory fence. See [crate#memory-ordering].
     #[inline]
-    fn next(&mut self) -> Option<&T> {
-        if self.index == self.len {
-            if unlikely(self.len == BLOCK_SIZE) {
-                // fetch next block, release current
-                if let Some(next_block) = self.block.try_load_next(Ordering::Acquire) {
-                    self.index = 0;
-                    self.len   = next_block.len.load(Ordering::Acquire);
-                    self.block = next_block;
-                    
-                    // TODO: Disallow empty blocks?
-                    if self.len == 0 {
-                        return None;
-                    }
-                } else {
-                    return None;
+    pub fn acquire_fence(&self) {
+        fence(Ordering::Acquire);
+    }
+    
+    /// Reader position - see [ReaderOffset].
+    #[inline]
+    pub fn offset(&self) -> ReaderOffset {
+        ReaderOffset(self.position() as u64)
+    }
+    
+    /// Moves reader forward to `offset`, skipping messages in between.
+    /// 
+    /// Fails with [Error::OffsetUnavailable], if `offset` is behind reader - 
+    /// blocks there may be already released. Or if it is past published 
+    /// messages. Reader is not moved then.
+    pub fn seek(&mut self, offset: ReaderOffset) -> Result<(), Error> {
+        let position = usize::try_from(offset.0).map_err(|_| Error::OffsetUnavailable)?;
+        if position < self.position() || position > self.published_position() {
+            return Err(Error::OffsetUnavailable);
+        }
+        self.skip_to(position);
+        Ok(())
+    }
+    
+    /// Clones messages in `[start, end)` offset range, without moving reader.
+    /// 
+    /// Only published messages are cloned - messages being written past
+    /// `end` are never touched. Fails with [Error::OffsetUnavailable], if
+    /// `start` is behind reader - blocks there may be already released.
+    /// Or if `end` is past published messages.
+    pub fn snapshot_range(&self, start: ReaderOffset, end: ReaderOffset) 
+        -> Result<Vec<T>, Error>
+    where
+        T: Clone
+    {
+        let start = usize::try_from(start.0).map_err(|_| Error::OffsetUnavailable)?;
+        let end   = usize::try_from(end.0).map_err(|_| Error::OffsetUnavailable)?;
+        if start < self.position() || end < start || end > self.published_position() {
+            return Err(Error::OffsetUnavailable);
+        }
+        
+        let mut values = Vec::with_capacity(end - start);
+        let mut block = self.block.clone();
+        let mut position = start;
+        while position < end {
+            while position >= block.start + block.capacity {
+                // Exists - everything before `end` is published.
+                block = block.try_load_next(Ordering::Acquire).unwrap();
+            }
+            let block_end = cmp::min(end, block.start + block.capacity);
+            for index in position - block.start..block_end - block.start {
+                values.push(unsafe{ &*block.mem().add(index) }.clone());
+            }
+            position = block_end;
+        }
+        Ok(values)
+    }
+    
+    /// Clones all unread published messages, without moving reader.
+    /// 
+    /// Consistent - end is fixed before the copy, and messages being written
+    /// after it are not touched. Rebuild the queue with [Queue::from_snapshot()].
+    /// 
+    /// Returned `Vec` is what you serialize, to warm-start a process.
+    pub fn snapshot(&self) -> Vec<T>
+    where
+        T: Clone
+    {
+        let end = ReaderOffset(self.published_position() as u64);
+        // Reader is never past published messages.
+        self.snapshot_range(self.offset(), end).unwrap()
+    }
+    
+    /// Position after the last published message.
+    fn published_position(&self) -> usize {
+        let mut block = self.block.clone();
+        loop {
+            let len = block.load_len();
+            if len == block.capacity {
+                if let Some(next_block) = block.try_load_next(Ordering::Acquire) {
+                    block = next_block;
+                    continue;
                 }
-            } else {
-                // Reread len.
-                // This is synchronization point. `mem` data should be in 
-                // current thread visibility, after `len` atomic load. 
-                // In analogue with spin-lock.
-                let block_len = self.block.len.load(Ordering::Acquire);
-                if self.len == block_len {
-                    // nothing changed.
-                    return None;
-                } 
-                self.len = block_len;
             }
+            return block.start + len;
         }
-        
-        unsafe{
-            let value = &*self.block.mem().add(self.index);
-            self.index += 1;
-            Some(value)
+    }
+    
+    /// Moves reader forward to published `position`.
+    fn skip_to(&mut self, position: usize) {
+        while position >= self.block.start + self.block.capacity {
+            match self.block.try_load_next(Ordering::Acquire) {
+                Some(next_block) => self.set_block(next_block),
+                None => break,
+            }
         }
+        self.index = position - self.block.start;
+        self.len   = cmp::max(self.len, self.index);
+    }
+    
+    /// Moves to the oldest block, queue keeps. 
+    /// See [Queue::with_max_blocks()].
+    #[cold]
+    #[inline(never)]
+    fn skip_dropped_blocks(&mut self) {
+        let oldest = self.state.subscriptions().oldest_position();
+        self.skipped += oldest - self.position();
+        self.skip_to(oldest);
+    }
+    
+    /// Number of messages, reader lost, because it fell behind 
+    /// [Queue::with_max_blocks()] limit.
+    #[inline]
+    pub fn skipped(&self) -> usize {
+        self.skipped
+    }
+    
+    #[inline]
+    fn report_position(&self) {
+        self.state.report(self.position());
+    }
+    
+    /// Makes reader, that skips whole blocks which metadata does not
+    /// match `predicate`.
+    /// 
+    /// `predicate` is called once per block, with block metadata.
+    /// See [Queue::set_block_meta()].
+    #[must_use]
+    #[inline]
+    pub fn filter_blocks<F>(self, predicate: F) -> BlockFilter<T, F>
+    where
+        F: FnMut(u64) -> bool
+    {
+        BlockFilter{
+            reader: self,
+            predicate,
+            checked_block: None,
+        }
+    }
+    
+    /// Sets `f` to be called each time reader moves to the next block,
+    /// with that block's sequence number (first block is 0).
+    /// 
+    /// Called from within [next()], so keep it cheap. Replaces previously
+    /// set callback. Not inherited by clones.
+    /// 
+    /// [next()]: LendingReader::next
+    #[inline]
+    pub fn on_block_advance(&mut self, f: impl FnMut(u64) + Send + Sync + 'static) {
+        self.on_block_advance = Some(Box::new(f));
+    }
+    
+    /// Moves reader forward to `other`'s position, if `other` is ahead.
+    /// Messages in between are skipped. If `other` is behind - does nothing.
+    /// 
+    /// # Panics
+    /// 
+    /// Panics if readers belong to different queues.
+    /// See [try_advance_to()] for non-panicking version.
+    /// 
+    /// [try_advance_to()]: Self::try_advance_to
+    #[inline]
+    pub fn advance_to(&mut self, other: &Reader<T>) {
+        if let Err(error) = self.try_advance_to(other) {
+            panic!("{error}");
+        }
+    }
+    
+    /// Same as [advance_to()], but returns [Error::DifferentQueues] 
+    /// instead of panic.
+    /// 
+    /// [advance_to()]: Self::advance_to
+    pub fn try_advance_to(&mut self, other: &Reader<T>) -> Result<(), Error> {
+        if other.position() > self.position() {
+            if !self.block.is_followed_by(&other.block) {
+                return Err(Error::DifferentQueues);
+            }
+            self.block = other.block.clone();
+            self.index = other.index;
+            self.len   = other.len;
+        } else if !other.block.is_followed_by(&self.block) {
+            return Err(Error::DifferentQueues);
+        }
+        Ok(())
+    }
+    
+    /// Same as [next()], but returns pinned reference.
+    /// 
+    /// Messages never move after being pushed, see [pinning].
+    /// 
+    /// [next()]: LendingReader::next
+    /// [pinning]: crate#pinning
+    #[inline]
+    pub fn next_pinned(&mut self) -> Option<Pin<&T>> {
+        // Values stay in place until dropped in place with their block.
+        self.next().map(|value| unsafe{ Pin::new_unchecked(value) })
+    }
+    
+    /// Calls `f` for each block this reader keeps alive - from its current 
+    /// block, to the last block of the queue.
+    /// 
+    /// Blocks before the reader's one, are not visible to it. To inspect the
+    /// whole queue, use the reader that is furthest behind.
+    /// 
+    /// This is read-only and best-effort - queue may change during the walk.
+    #[inline]
+    pub fn for_each_block(&self, f: impl FnMut(&BlockInfo)) {
+        self.block.for_each(f)
+    }
+    
+    /// Number of messages pushed to `queue`, that reader did not consume yet.
+    /// 
+    /// `queue` must be the queue reader belongs to.
+    #[inline]
+    pub fn catch_up_count<const N: usize>(&self, queue: &Queue<T, N>) -> usize {
+        queue.write_position().saturating_sub(self.position())
+    }
+    
+    /// Number of published messages, that reader did not consume yet.
+    /// Same as [catch_up_count()], but without the queue.
+    /// 
+    /// Walks blocks from reader to the queue end. Snapshot - writers may 
+    /// push during the walk. 0 at the queue end.
+    /// 
+    /// [catch_up_count()]: Self::catch_up_count
+    #[inline]
+    pub fn lag(&self) -> usize {
+        self.published_position().saturating_sub(self.position())
+    }
+    
+    /// Drops the backlog - moves reader to the queue end, as if it was
+    /// just constructed by [Queue::reader()]. Reads only messages pushed 
+    /// from now on.
+    /// 
+    /// Messages between old position and the queue end are permanently 
+    /// skipped for this reader.
+    /// 
+    /// Walks blocks from reader to the queue end - as [lag()].
+    /// 
+    /// [lag()]: Self::lag
+    pub fn skip_to_tail(&mut self) {
+        self.skip_to(self.published_position());
+        self.report_position();
+    }
+    
+    /// Queue is closed, and reader consumed everything pushed before close.
+    /// Reader will never return a message again.
+    /// 
+    /// Walks blocks from reader to the queue end - as [lag()].
+    /// 
+    /// [lag()]: Self::lag
+    pub fn is_closed_and_drained(&self) -> bool {
+        // Close is SeqCst - everything pushed before it is visible now.
+        self.state.subscriptions().is_closed() && self.lag() == 0
+    }
+    
+    /// Pointer to, and length of, the ready messages in the current block,
+    /// starting from reader's position. Reader is not advanced.
+    /// 
+    /// Messages in a block are contiguous, so this can be passed as is to 
+    /// FFI code, without copying. Ready region never spans several blocks -
+    /// it can be shorter than [catch_up_count()].
+    /// 
+    /// Getting the pointer is safe - dereferencing it is not. Pointer is 
+    /// valid for reads of `len` messages only while the reader stays in 
+    /// the current block - that is, until the next mutating call on 
+    /// the reader ([next()], [advance_to()], drop, etc.). Messages must not
+    /// be mutated or moved out through it.
+    /// 
+    /// [next()]: LendingReader::next
+    /// [advance_to()]: Self::advance_to
+    /// [catch_up_count()]: Self::catch_up_count
+    #[inline]
+    pub fn current_block_slice(&self) -> (*const T, usize) {
+        let len = self.block.load_len();
+        let ptr = unsafe{ self.block.mem().add(self.index) };
+        (ptr, len - self.index)
+    }
+    
+    /// Ready messages in the current block, from reader's position. 
+    /// Reader advances past them.
+    /// 
+    /// Never crosses block boundary - at the block end, iterator is empty, 
+    /// and [next()] moves to the next block. Ends where [current_block_slice()]
+    /// does.
+    /// 
+    /// [next()]: LendingReader::next
+    /// [current_block_slice()]: Self::current_block_slice
+    pub fn block_remaining_iter(&mut self) -> impl ExactSizeIterator<Item = &T> {
+        let (ptr, len) = self.current_block_slice();
+        self.index += len;
+        self.len = self.index;
+        unsafe{ slice::from_raw_parts(ptr, len) }.iter()
+    }
+    
+    /// Returns the whole next block, if reader is at block boundary, and 
+    /// the block is full. Reader advances past it.
+    /// 
+    /// Returns `None` if block is not full yet, or reader is in the middle 
+    /// of block - use [next()] to reach the boundary then.
+    /// 
+    /// [next()]: LendingReader::next
+    pub fn next_block(&mut self) -> Option<&[T]> {
+        if self.index == self.block.capacity {
+            let next_block = self.block.try_load_next(Ordering::Acquire)?;
+            self.advance_block(next_block);
+        }
+        let capacity = self.block.capacity;
+        if self.index != 0 || self.block.load_len() != capacity {
+            return None;
+        }
+        self.index = capacity;
+        self.len   = capacity;
+        // Block-only readers must be seen by quiesce() and bounded writers too.
+        self.report_position();
+        Some(unsafe{ slice::from_raw_parts(self.block.mem(), capacity) })
+    }
+    
+    /// Same as [next()], but message lives as long as returned guard - 
+    /// not until the next read. So several messages can be held at once.
+    /// 
+    /// ```
+    /// let mut queue = chute::spmc::Queue::new();
+    /// let mut reader = queue.reader();
+    /// queue.push(1);
+    /// queue.push(2);
+    /// let first  = reader.next_guard().unwrap();
+    /// let second = reader.next_guard().unwrap();
+    /// assert_eq!((*first, *second), (1, 2));
+    /// ```
+    /// 
+    /// Each guard is an atomic increment/decrement of the block use counter. 
+    /// 
+    /// [next()]: LendingReader::next
+    #[inline]
+    pub fn next_guard(&mut self) -> Option<ReadGuard<T>> {
+        let value: *const T = self.next()?;
+        // `value` is in the current block.
+        Some(unsafe{ ReadGuard::new(self.block.clone(), value) })
+    }
+    
+    /// Same as [next()], but with message index - number of messages pushed
+    /// to the queue before it. 
+    /// 
+    /// All readers see a message under the same index. It is the [offset()]
+    /// reader had right before reading the message.
+    /// 
+    /// ```
+    /// # use chute::LendingReader;
+    /// let mut queue = chute::spmc::Queue::new();
+    /// queue.push(0);
+    /// let mut reader = queue.reader();
+    /// queue.push(1);
+    /// queue.push(2);
+    /// assert_eq!(reader.next_indexed(), Some((1, &1)));
+    /// assert_eq!(reader.next_indexed(), Some((2, &2)));
+    /// ```
+    /// 
+    /// [next()]: LendingReader::next
+    /// [offset()]: Self::offset
+    #[inline]
+    pub fn next_indexed(&mut self) -> Option<(u64, &T)> {
+        let value: *const T = self.next()?;
+        // Reader is right past the message.
+        let index = (self.position() - 1) as u64;
+        Some((index, unsafe{ &*value }))
+    }
+    
+    /// Ready messages in the current block, from reader's position, as one slice. 
+    /// Reader advances past them.
+    /// 
+    /// Like [next()], but for a run of messages - moves to the next block 
+    /// when the current one is read. Never crosses block boundary - 
+    /// the rest of the run comes with the following call.
+    /// Returns `None` if there is nothing new.
+    /// 
+    /// ```
+    /// # use chute::LendingReader;
+    /// let mut queue = chute::spmc::Queue::new();
+    /// let mut reader = queue.reader();
+    /// queue.push(1);
+    /// queue.push(2);
+    /// assert_eq!(reader.next_chunk(), Some(&[1, 2][..]));
+    /// assert_eq!(reader.next_chunk(), None);
+    /// ```
+    /// 
+    /// [next()]: LendingReader::next
+    #[inline]
+    pub fn next_chunk(&mut self) -> Option<&[T]> {
+        // Moves to the next block and rereads len, if needed.
+        self.next()?;
+        self.unread();
+        let start = self.index;
+        self.index = self.len;
+        Some(unsafe{ slice::from_raw_parts(self.block.mem().add(start), self.len - start) })
+    }
+    
+    /// Move to the start of `block`.
+    #[inline]
+    fn set_block(&mut self, block: BlockArc<T>) {
+        self.index = 0;
+        self.len   = block.load_len();
+        self.block = block;
+        if let Some(f) = &mut self.on_block_advance {
+            f(self.block.seq as u64);
+        }
+    }
+    
+    /// Move to the next `block`, as reader leaves the current one.
+    /// 
+    /// Same as [set_block()], but skips blocks that queue dropped 
+    /// meanwhile. All block switches on read should go through this.
+    /// 
+    /// [set_block()]: Self::set_block
+    #[inline]
+    fn advance_block(&mut self, block: BlockArc<T>) {
+        self.set_block(block);
+        if unlikely(self.block.start < self.state.subscriptions().oldest_position()) {
+            self.skip_dropped_blocks();
+        }
+        // Keep Queue::approx_len() stale by less than a block.
+        self.report_position();
+    }
+    
+    /// Converts to [CopyingReader] - cloning reader, that releases
+    /// consumed blocks eagerly.
+    #[must_use]
+    #[inline]
+    pub fn copying(self) -> CopyingReader<Self> {
+        CopyingReader{ reader: self }
+    }
+    
+    /// If current block is read to the end, and there is next one - 
+    /// moves to it, releasing current block.
+    #[inline]
+    fn leave_finished_block(&mut self) {
+        if self.index == self.block.capacity {
+            if let Some(next_block) = self.block.try_load_next(Ordering::Acquire) {
+                self.advance_block(next_block);
+            }
+        }
+    }
+    
+    /// Moves unread messages out of the queue, consuming reader.
+    /// 
+    /// Messages are moved out only from blocks, that this reader owns exclusively - 
+    /// iteration stops at the first block, that is shared with some other reader,
+    /// writer or the queue itself. So to salvage all messages, drop the queue,
+    /// all writers and other readers first.
+    /// 
+    /// Messages that are not moved out, are dropped with their blocks as usual.
+    /// 
+    /// Requires `T: Unpin` - moving out would break [pinning] guarantee:
+    /// ```compile_fail
+    /// # use std::marker::PhantomPinned;
+    /// let queue = chute::spmc::Queue::<PhantomPinned>::new();
+    /// let reader = queue.reader();
+    /// drop(reader.into_drain());
+    /// ```
+    /// 
+    /// [pinning]: crate#pinning
+    #[inline]
+    pub fn into_drain(self) -> impl Iterator<Item = T>
+    where
+        T: Unpin
+    {
+        BlockDrain::new(self.block, self.index)
+    }
+    
+    /// [into_drain()] of each reader, chained - reader by reader, in order.
+    /// 
+    /// For shutdown, with one reader per queue: drop queues and writers, 
+    /// then drain what is left into one output.
+    /// 
+    /// [into_drain()]: Self::into_drain
+    #[inline]
+    pub fn drain_all(readers: impl IntoIterator<Item = Self>) -> impl Iterator<Item = T>
+    where
+        T: Unpin
+    {
+        readers.into_iter().flat_map(Self::into_drain)
+    }
+    
+    /// Steps reader back by one message, so the next [next()] returns it again.
+    ///
+    /// Un-reading the message, most recently returned by [next()], always
+    /// succeeds. You can keep stepping back, but only within the current block -
+    /// returns `false` when reader is at the start of its block.
+    ///
+    /// [next()]: LendingReader::next
+    #[inline]
+    pub fn unread(&mut self) -> bool {
+        if self.index == 0 {
+            return false;
+        }
+        self.index -= 1;
+        true
+    }
+    
+    /// Is there something new to read?
+    /// 
+    /// SeqCst, for Notifier.
+    #[cfg(any(feature = "std", feature = "async"))]
+    fn has_news(&self) -> bool {
+        if self.index != self.len {
+            return true;
+        }
+        if self.len == self.block.capacity {
+            return !self.block.next.load(Ordering::SeqCst).is_null();
+        }
+        self.block.len.load(Ordering::SeqCst) != self.len
+    }
+    
+    /// Blocks until there is something new to read.
+    /// 
+    /// Thread is parked, instead of spinning. Woken up by [Queue::push_notify()],
+    /// [Queue::notify()], [Queue::push_slice()] and [Writer] drop. Messages 
+    /// pushed with [Queue::push()] do not wake reader, until [Queue::notify()].
+    /// 
+    /// Returns immediately, if there is something to read already, or 
+    /// queue is dropped. After return, [next()] may still return `None`, 
+    /// if the only news was a new block.
+    /// 
+    /// [next()]: LendingReader::next
+    #[cfg(feature = "std")]
+    #[inline]
+    pub fn wait(&self) {
+        let subscriptions = self.state.subscriptions();
+        if self.has_news() || subscriptions.is_closed() {
+            return;
+        }
+        subscriptions.message_notifier.wait_while(|| 
+            !self.has_news() && !subscriptions.is_closed()
+        );
+    }
+    
+    /// Same as [next()], but tells apart empty queue - `Ok(None)`, and 
+    /// closed one - [Error::Closed].
+    /// 
+    /// Queue is closed, when it is dropped. [Error::Closed] is returned
+    /// after everything pushed before that is read. 
+    /// Costs nothing extra, while there are messages.
+    /// 
+    /// [next()]: LendingReader::next
+    #[inline]
+    pub fn try_next(&mut self) -> Result<Option<&T>, Error> {
+        if let Some(value) = self.next() {
+            // NLL can not return conditional borrow, and then borrow again.
+            let value: *const T = value;
+            return Ok(Some(unsafe{ &*value }));
+        }
+        if !self.state.subscriptions().is_closed() {
+            return Ok(None);
+        }
+        // Everything pushed before close is visible now.
+        self.next().map(Some).ok_or(Error::Closed)
+    }
+    
+    /// Blocks until the next message, and returns it. `None` - if queue 
+    /// is closed, and everything is read. See [try_next()].
+    /// 
+    /// Does not block, if there is a message already. Otherwise parks
+    /// with [wait()] - so only wakes on [Queue::push_notify()], 
+    /// [Queue::notify()] and the rest listed there.
+    /// 
+    /// [try_next()]: Self::try_next
+    /// [wait()]: Self::wait
+    #[cfg(feature = "std")]
+    #[inline]
+    pub fn recv(&mut self) -> Option<&T> {
+        loop {
+            match self.try_next().map(|value| value.map(|value| value as *const T)) {
+                // NLL can not return conditional borrow, and then borrow again.
+                Ok(Some(value)) => return Some(unsafe{ &*value }),
+                Ok(None) => self.wait(),
+                Err(_) => return None,
+            }
+        }
+    }
+    
+    /// Converts to async [Stream] of cloned messages. See [ReaderStream].
+    /// 
+    /// [Stream]: futures_core::Stream
+    /// [ReaderStream]: crate::ReaderStream
+    #[cfg(feature = "async")]
+    #[inline]
+    pub fn into_stream(self) -> crate::ReaderStream<Self> {
+        crate::ReaderStream{ reader: self }
+    }
+}
+
+impl<T> Clone for Reader<T> {
+    #[inline]
+    fn clone(&self) -> Self {
+        Self{
+            block: self.block.clone(),
+            index: self.index,
+            len  : self.len,
+            state: self.state.register_clone(self.position()),
+            on_block_advance: None,
+            skipped: self.skipped,
+        }
+    }
+}
+
+impl<T> LendingReader for Reader<T>{
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<&T> {
+        if self.index == self.len {
+            if unlikely(self.len == self.block.capacity) {
+                // fetch next block, release current
+                if let Some(next_block) = self.block.try_load_next(Ordering::Acquire) {
+                    self.advance_block(next_block);
+                    
+                    // TODO: Disallow empty blocks?
+                    if self.len == 0 {
+                        self.report_position();
+                        return None;
+                    }
+                } else {
+                    self.report_position();
+                    return None;
+                }
+            } else {
+                // Reread len.
+                // This is synchronization point. `mem` data should be in 
+                // current thread visibility, after `len` atomic load. 
+                // In analogue with spin-lock.
+                let block_len = self.block.load_len();
+                if self.len == block_len {
+                    // nothing changed.
+                    self.report_position();
+                    return None;
+                } 
+                self.len = block_len;
+            }
+        }
+        
+        unsafe{
+            let value = &*self.block.mem().add(self.index);
+            self.index += 1;
+            Some(value)
+        }
+    }
+}
+
+impl<T> PeekingReader for Reader<T> {
+    #[inline]
+    fn peek(&mut self) -> Option<&T> {
+        self.next()?;
+        self.unread();
+        Some(unsafe{ &*self.block.mem().add(self.index) })
+    }
+}
+
+impl<T: Clone> Iterator for CopyingReader<Reader<T>> {
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        // Clone before advancing - panicking clone must not lose the message.
+        let value = self.reader.peek().cloned();
+        if value.is_some() {
+            self.reader.next();
+        }
+        self.reader.leave_finished_block();
+        value
+    }
+}
+
+#[cfg(feature = "async")]
+impl<T: Clone> futures_core::Stream for crate::ReaderStream<Reader<T>> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut core::task::Context<'_>) 
+        -> core::task::Poll<Option<T>> 
+    {
+        use core::task::Poll;
+        let reader = &mut self.get_mut().reader;
+        loop {
+            match reader.try_next() {
+                Ok(Some(value)) => return Poll::Ready(Some(value.clone())),
+                Ok(None) => {},
+                Err(_) => return Poll::Ready(None),
+            }
+            let subscriptions = reader.state.subscriptions();
+            subscriptions.message_notifier.register(cx.waker());
+            // Re-check after registration, in case push was before it.
+            if !reader.has_news() && !subscriptions.is_closed() {
+                return Poll::Pending;
+            }
+        }
+    }
+}
+
+impl<T, const N: usize> Drop for Queue<T, N> {
+    #[inline]
+    fn drop(&mut self) {
+        self.subscriptions.close();
+    }
+}
+
+/// Reader that skips whole blocks by metadata.
+/// 
+/// Constructed by [Reader::filter_blocks()].
+pub struct BlockFilter<T, F>{
+    reader: Reader<T>,
+    predicate: F,
+    /// Sequence number of the block, [predicate] was called for.
+    checked_block: Option<usize>,
+}
+
+impl<T, F> LendingReader for BlockFilter<T, F>
+where
+    F: FnMut(u64) -> bool
+{
+    type Item = T;
+    
+    #[inline]
+    fn next(&mut self) -> Option<&T> {
+        loop {
+            let reader = &mut self.reader;
+            if self.checked_block != Some(reader.block.seq) {
+                self.checked_block = Some(reader.block.seq);
+                let meta = reader.block.meta.load(Ordering::Relaxed);
+                if !(self.predicate)(meta) {
+                    // Skip to the block end.
+                    reader.index = reader.block.capacity;
+                    reader.len   = reader.block.capacity;
+                }
+            }
+            
+            // Switch block here, to check it before reading.
+            if reader.index != reader.block.capacity {
+                break;
+            }
+            if let Some(next_block) = reader.block.try_load_next(Ordering::Acquire) {
+                reader.advance_block(next_block);
+            } else {
+                reader.report_position();
+                return None;
+            }
+        }
+        self.reader.next()
+    }
+}
+
+/// Block detached from the queue. Iterates its messages by value.
+/// 
+/// Messages that are not moved out, are dropped with it.
+/// 
+/// Constructed by [Queue::take_full_block()].
+pub struct OwnedBlock<T: Unpin>{
+    /// Never advanced past `len` - next block belongs to the queue.
+    drain: BlockDrain<T>,
+    len: usize,
+}
+
+impl<T: Unpin> Iterator for OwnedBlock<T> {
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+        self.len -= 1;
+        self.drain.next()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T: Unpin> ExactSizeIterator for OwnedBlock<T> {}
+
+#[cfg(test)]
+mod test{
+    use std::sync::{Arc, Mutex};
+    use rand::{Rng, SeedableRng};
+    use crate::block::BLOCK_SIZE;
+    use crate::spmc::{Queue, Reader};
+    use crate::LendingReader;
+    use crate::test::{CountingAllocator, StringWrapper};
+
+    #[test]
+    fn swap_test() {
+        let mut queue: Queue<usize> = Queue::new();
+        let mut old_reader = queue.reader();
+        queue.push(1);
+        queue.push(2);
+
+        let mut new_queue = Queue::new();
+        new_queue.push(100);
+        let old_queue = queue.swap(new_queue);
+        drop(old_queue);
+
+        let mut new_reader = queue.reader();
+        queue.push(3);
+
+        assert_eq!(old_reader.next(), Some(&1));
+        assert_eq!(old_reader.next(), Some(&2));
+        assert_eq!(old_reader.next(), None);
+
+        assert_eq!(new_reader.next(), Some(&3));
+        assert_eq!(new_reader.next(), None);
+    }
+
+    #[test]
+    fn unread_test() {
+        let mut queue: Queue<usize> = Queue::new();
+        let mut reader = queue.reader();
+        assert!(!reader.unread());
+
+        for i in 0..BLOCK_SIZE+1 {
+            queue.push(i);
+        }
+        assert_eq!(reader.next(), Some(&0));
+        assert!(reader.unread());
+        assert_eq!(reader.next(), Some(&0));
+
+        for i in 1..BLOCK_SIZE {
+            assert_eq!(reader.next(), Some(&i));
+        }
+        assert_eq!(reader.next(), Some(&BLOCK_SIZE));
+        assert!(reader.unread());
+        assert!(!reader.unread());
+        assert_eq!(reader.next(), Some(&BLOCK_SIZE));
+        assert_eq!(reader.next(), None);
+    }
+    
+    #[test]
+    fn try_next_test() {
+        use crate::Error;
+        
+        let mut queue: Queue<usize> = Queue::new();
+        let mut reader = queue.reader();
+        assert_eq!(reader.try_next(), Ok(None));
+        for i in 0..BLOCK_SIZE+1 {
+            queue.push(i);
+        }
+        assert_eq!(reader.try_next(), Ok(Some(&0)));
+        
+        // Old queue closes on swap.
+        drop(queue.swap(Queue::new()));
+        for i in 1..BLOCK_SIZE+1 {
+            assert_eq!(reader.try_next(), Ok(Some(&i)));
+        }
+        assert_eq!(reader.try_next(), Err(Error::Closed));
+        assert_eq!(reader.try_next(), Err(Error::Closed));
+        
+        let mut reader = queue.reader();
+        queue.push(1);
+        assert_eq!(reader.try_next(), Ok(Some(&1)));
+        assert_eq!(reader.try_next(), Ok(None));
+    }
+    
+    #[cfg(feature = "async")]
+    #[test]
+    fn stream_test() {
+        use std::pin::pin;
+        use std::sync::atomic::Ordering;
+        use std::task::{Context, Poll, Waker};
+        use futures_core::Stream;
+        use crate::test::CountingWaker;
+        
+        let waker: Arc<CountingWaker> = Default::default();
+        let std_waker = Waker::from(waker.clone());
+        let mut cx = Context::from_waker(&std_waker);
+        let mut queue: Queue<usize> = Queue::new();
+        let mut stream = pin!(queue.reader().into_stream());
+        
+        assert_eq!(stream.as_mut().poll_next(&mut cx), Poll::Pending);
+        assert_eq!(stream.as_mut().poll_next(&mut cx), Poll::Pending);
+        queue.push(1);
+        assert_eq!(waker.wakes.load(Ordering::SeqCst), 0);
+        queue.push_notify(2);
+        // Registered once.
+        assert_eq!(waker.wakes.load(Ordering::SeqCst), 1);
+        assert_eq!(stream.as_mut().poll_next(&mut cx), Poll::Ready(Some(1)));
+        assert_eq!(stream.as_mut().poll_next(&mut cx), Poll::Ready(Some(2)));
+        
+        for i in 0..BLOCK_SIZE+10 {
+            queue.push_notify(i);
+        }
+        assert_eq!(waker.wakes.load(Ordering::SeqCst), 1);
+        for i in 0..BLOCK_SIZE+10 {
+            assert_eq!(stream.as_mut().poll_next(&mut cx), Poll::Ready(Some(i)));
+        }
+        assert_eq!(stream.as_mut().poll_next(&mut cx), Poll::Pending);
+        // Batch push wakes.
+        queue.push_slice(&[3, 4]);
+        assert_eq!(waker.wakes.load(Ordering::SeqCst), 2);
+        assert_eq!(stream.as_mut().poll_next(&mut cx), Poll::Ready(Some(3)));
+        assert_eq!(stream.as_mut().poll_next(&mut cx), Poll::Ready(Some(4)));
+        assert_eq!(stream.as_mut().poll_next(&mut cx), Poll::Pending);
+        drop(queue);
+        assert_eq!(waker.wakes.load(Ordering::SeqCst), 3);
+        assert_eq!(stream.as_mut().poll_next(&mut cx), Poll::Ready(None));
+    }
+    
+    #[test]
+    #[cfg(feature = "std")]
+    fn recv_test() {
+        let mut queue: Queue<usize> = Queue::new();
+        const COUNT: usize = BLOCK_SIZE * 2 + 10;
+        let readers: Vec<_> = (0..2).map(|_| {
+            let mut reader = queue.reader();
+            std::thread::spawn(move || {
+                let mut values = Vec::new();
+                while let Some(value) = reader.recv() {
+                    values.push(*value);
+                }
+                values
+            })
+        }).collect();
+        
+        for i in 0..COUNT {
+            if i % 100 == 0 {
+                // Let readers fall asleep.
+                std::thread::sleep(std::time::Duration::from_millis(1));
+                queue.push_notify(i);
+            } else {
+                queue.push(i);
+            }
+        }
+        queue.notify();
+        drop(queue);
+        
+        for reader in readers {
+            assert!(reader.join().unwrap().into_iter().eq(0..COUNT));
+        }
+    }
+
+    #[test]
+    fn current_block_slice_test() {
+        let mut queue: Queue<usize> = Queue::new();
+        let mut reader = queue.reader();
+        for i in 0..BLOCK_SIZE+10 {
+            queue.push(i);
+        }
+        assert_eq!(reader.next(), Some(&0));
+        
+        let (ptr, len) = reader.current_block_slice();
+        let slice = unsafe{ std::slice::from_raw_parts(ptr, len) };
+        assert!(slice.iter().copied().eq(1..BLOCK_SIZE));
+        
+        for _ in 0..BLOCK_SIZE {
+            reader.next();
+        }
+        let (ptr, len) = reader.current_block_slice();
+        let slice = unsafe{ std::slice::from_raw_parts(ptr, len) };
+        assert!(slice.iter().copied().eq(BLOCK_SIZE+1..BLOCK_SIZE+10));
+    }
+
+    #[test]
+    fn snapshot_range_test() {
+        use crate::{Error, ReaderOffset};
+        
+        let mut queue: Queue<usize> = Queue::new();
+        let mut reader = queue.reader();
+        for i in 0..BLOCK_SIZE*2 {
+            queue.push(i);
+        }
+        let values = reader.snapshot_range(ReaderOffset(10), ReaderOffset((BLOCK_SIZE+10) as u64)).unwrap();
+        assert!(values.iter().copied().eq(10..BLOCK_SIZE+10));
+        assert_eq!(reader.next(), Some(&0));
+        
+        assert_eq!(reader.snapshot_range(ReaderOffset(0), ReaderOffset(5)), Err(Error::OffsetUnavailable));
+        assert_eq!(reader.snapshot_range(ReaderOffset(5), ReaderOffset((BLOCK_SIZE*2+1) as u64)), Err(Error::OffsetUnavailable));
+        assert_eq!(reader.snapshot_range(ReaderOffset(5), ReaderOffset(5)), Ok(Vec::new()));
+    }
+    
+    #[test]
+    fn snapshot_test() {
+        let mut queue: Queue<usize, 64> = Queue::default();
+        let mut reader = queue.reader();
+        for i in 0..64*3+10 {
+            queue.push(i);
+        }
+        for _ in 0..20 {
+            reader.next();
+        }
+        let snapshot = reader.snapshot();
+        assert!(snapshot.iter().copied().eq(20..64*3+10));
+        // Reader not moved.
+        assert_eq!(reader.next(), Some(&20));
+        
+        let (mut queue, mut reader) = Queue::<usize, 64>::from_snapshot(snapshot);
+        queue.push(1000);
+        assert!(std::iter::from_fn(|| reader.next().copied()).eq((20..64*3+10).chain([1000])));
+        assert_eq!(reader.snapshot(), Vec::new());
+    }
+    
+    #[test]
+    fn block_remaining_iter_test() {
+        let mut queue: Queue<usize> = Queue::new();
+        let mut reader = queue.reader();
+        for i in 0..BLOCK_SIZE+10 {
+            queue.push(i);
+        }
+        assert_eq!(reader.next(), Some(&0));
+        
+        let iter = reader.block_remaining_iter();
+        assert_eq!(iter.len(), BLOCK_SIZE-1);
+        assert!(iter.copied().eq(1..BLOCK_SIZE));
+        // Does not cross block boundary.
+        assert_eq!(reader.block_remaining_iter().len(), 0);
+        
+        assert_eq!(reader.next(), Some(&BLOCK_SIZE));
+        let iter = reader.block_remaining_iter();
+        assert!(iter.copied().eq(BLOCK_SIZE+1..BLOCK_SIZE+10));
+        assert_eq!(reader.next(), None);
+        
+        queue.push(100);
+        assert_eq!(reader.next(), Some(&100));
+    }
+
+    #[test]
+    fn quiesce_test() {
+        let mut queue: Queue<usize> = Queue::new();
+        let mut reader = queue.reader();
+        for i in 0..BLOCK_SIZE {
+            queue.push(i);
+        }
+        
+        std::thread::scope(|s| {
+            s.spawn(|| {
+                while reader.next().is_some() {}
+            });
+            queue.quiesce();
+        });
+        assert!(reader.next().is_none());
+    }
+    
+    #[test]
+    fn quiesce_next_block_test() {
+        let mut queue: Queue<usize> = Queue::new();
+        let mut reader = queue.reader();
+        for i in 0..BLOCK_SIZE*2 {
+            queue.push(i);
+        }
+        
+        std::thread::scope(|s| {
+            s.spawn(|| {
+                assert!(reader.next_block().unwrap().iter().copied().eq(0..BLOCK_SIZE));
+                assert!(reader.next_block().unwrap().iter().copied().eq(BLOCK_SIZE..BLOCK_SIZE*2));
+            });
+            queue.quiesce();
+        });
+    }
+
+    #[test]
+    fn filter_blocks_test() {
+        let mut queue: Queue<usize> = Queue::new();
+        let reader = queue.reader();
+        let mut reader = reader.filter_blocks(|meta| meta != 1);
+        
+        // block 0 - meta 0, block 1 - meta 1, block 2 - meta 2
+        for i in 0..BLOCK_SIZE {
+            queue.push(i);
+        }
+        queue.set_block_meta(1);
+        for i in BLOCK_SIZE..BLOCK_SIZE*2 {
+            queue.push(i);
+        }
+        queue.set_block_meta(2);
+        queue.push(BLOCK_SIZE*2);
+        
+        let mut values = Vec::new();
+        while let Some(value) = reader.next() {
+            values.push(*value);
+        }
+        let expected: Vec<usize> = (0..BLOCK_SIZE).chain([BLOCK_SIZE*2]).collect();
+        assert_eq!(values, expected);
+    }
+
+    #[test]
+    fn next_pinned_test() {
+        use std::marker::PhantomPinned;
+        struct Pinned(usize, PhantomPinned);
+        
+        let mut queue = Queue::new();
+        let mut reader1 = queue.reader();
+        let mut reader2 = queue.reader();
+        for i in 0..BLOCK_SIZE+1 {
+            queue.push(Pinned(i, PhantomPinned));
+        }
+        for i in 0..BLOCK_SIZE+1 {
+            let value1: *const Pinned = reader1.next_pinned().unwrap().get_ref();
+            let value2 = reader2.next_pinned().unwrap();
+            assert_eq!(value2.0, i);
+            assert!(std::ptr::eq(value1, value2.get_ref()));
+        }
+    }
+
+    #[test]
+    fn for_each_block_test() {
+        let mut queue: Queue<usize> = Queue::new();
+        let reader = queue.reader();
+        for i in 0..BLOCK_SIZE*2 + 10 {
+            queue.push(i);
+        }
+        let _reader2 = queue.reader();
+        
+        let mut infos = Vec::new();
+        reader.for_each_block(|info| infos.push(info.clone()));
+        
+        assert_eq!(infos.len(), 3);
+        for (seq, info) in infos.iter().enumerate() {
+            assert_eq!(info.seq, seq);
+            assert_eq!(info.capacity, BLOCK_SIZE);
+        }
+        assert_eq!(infos[0].len, BLOCK_SIZE);
+        assert_eq!(infos[2].len, 10);
+        // reader
+        assert_eq!(infos[0].use_count, 1);
+        // prev block + queue + reader2
+        assert_eq!(infos[2].use_count, 3);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn adaptive_blocks_test() {
+        use crate::growth::MIN_ADAPTIVE_CAPACITY;
+
+        const COUNT: usize = BLOCK_SIZE * 4;
+        let mut queue: Queue<usize> = Queue::default().with_adaptive_blocks();
+        let mut reader = queue.reader();
+        for i in 0..COUNT {
+            queue.push(i);
+        }
+        assert_eq!(reader.catch_up_count(&queue), COUNT);
+
+        let mut infos = Vec::new();
+        reader.for_each_block(|info| infos.push(info.clone()));
+        assert_eq!(infos[0].capacity, MIN_ADAPTIVE_CAPACITY);
+        for info in &infos {
+            assert!(info.capacity >= MIN_ADAPTIVE_CAPACITY && info.capacity <= BLOCK_SIZE);
+            assert_eq!(info.capacity % 64, 0);
+        }
+        assert_eq!(infos.iter().map(|info| info.len).sum::<usize>(), COUNT);
+        if !cfg!(miri) {
+            // Pushed in a tight loop - blocks should grow.
+            assert!(infos.iter().any(|info| info.capacity > MIN_ADAPTIVE_CAPACITY));
+        }
+
+        for i in 0..COUNT {
+            assert_eq!(reader.next(), Some(&i));
+        }
+        assert_eq!(reader.next(), None);
+        assert_eq!(reader.catch_up_count(&queue), 0);
+    }
+    
+    #[test]
+    #[cfg(feature = "std")]
+    fn block_size_test() {
+        let mut queue: Queue<usize, 64> = Default::default();
+        let mut reader = queue.reader();
+        for i in 0..200 {
+            queue.push(i);
+        }
+        let mut infos = Vec::new();
+        reader.for_each_block(|info| infos.push(info.clone()));
+        assert_eq!(infos.len(), 4);
+        assert!(infos.iter().all(|info| info.capacity == 64));
+        assert!(std::iter::from_fn(|| reader.next().copied()).eq(0..200));
+        
+        // Adaptive blocks do not grow past block size.
+        let mut queue: Queue<usize, 128> = Queue::default().with_adaptive_blocks();
+        let reader = queue.reader();
+        for i in 0..1000 {
+            queue.push(i);
+        }
+        reader.for_each_block(|info| assert!(info.capacity <= 128));
+    }
+
+    #[test]
+    fn is_single_block_test() {
+        let mut queue: Queue<usize> = Queue::new();
+        for i in 0..BLOCK_SIZE {
+            queue.push(i);
+        }
+        assert!(queue.is_single_block());
+        queue.push(BLOCK_SIZE);
+        assert!(!queue.is_single_block());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn block_allocator_test() {
+        use std::sync::atomic::Ordering;
+        
+        let allocator: Arc<CountingAllocator> = Default::default();
+        let mut queue: Queue<usize> = Queue::new()
+            .with_block_allocator(allocator.clone())
+            .with_adaptive_blocks();
+        // Replaced first block is freed.
+        assert_eq!(allocator.allocs.load(Ordering::Relaxed), 2);
+        assert_eq!(allocator.deallocs.load(Ordering::Relaxed), 1);
+        
+        for i in 0..BLOCK_SIZE {
+            queue.push(i);
+        }
+        drop(queue);
+        let allocs = allocator.allocs.load(Ordering::Relaxed);
+        assert!(allocs > 2);
+        assert_eq!(allocator.deallocs.load(Ordering::Relaxed), allocs);
+    }
+    
+    #[test]
+    #[cfg(feature = "allocator_api")]
+    fn allocator_test() {
+        use std::sync::atomic::Ordering;
+        
+        let allocator: &'static CountingAllocator = Box::leak(Default::default());
+        let mut queue: Queue<usize> = Queue::new().with_allocator(allocator);
+        let mut reader = queue.reader();
+        for i in 0..BLOCK_SIZE*2 {
+            queue.push(i);
+        }
+        drop(queue);
+        // Blocks are freed with allocator, after the queue is gone.
+        while reader.next().is_some() {}
+        drop(reader);
+        let allocs = allocator.allocs.load(Ordering::Relaxed);
+        assert_eq!(allocs, 2);
+        assert_eq!(allocator.deallocs.load(Ordering::Relaxed), allocs);
+    }
+    
+    #[test]
+    fn next_lock_free_test() {
+        use std::sync::mpsc;
+        use std::time::Duration;
+        
+        let mut queue: Queue<usize> = Queue::new();
+        let mut reader = queue.reader();
+        for i in 0..BLOCK_SIZE*2 + 10 {
+            queue.push(i);
+        }
+        
+        // Reading should not take any of queue locks.
+        let locks = queue.subscriptions.lock_all();
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            let mut count = 0;
+            while reader.next().is_some() {
+                count += 1;
+            }
+            sender.send(count).unwrap();
+        });
+        let count = receiver.recv_timeout(Duration::from_secs(10))
+            .expect("Reader blocked on lock.");
+        assert_eq!(count, BLOCK_SIZE*2 + 10);
+        drop(locks);
+    }
+    
+    #[test]
+    fn rollover_threshold_test() {
+        use std::sync::atomic::Ordering;
+        
+        let allocator: Arc<CountingAllocator> = Default::default();
+        let mut queue: Queue<StringWrapper> = Queue::new()
+            .with_block_allocator(allocator.clone())
+            .with_rollover_threshold(0.5);
+        let mut reader = queue.reader();
+        let allocs = || allocator.allocs.load(Ordering::Relaxed);
+        
+        for i in 0..BLOCK_SIZE/2 {
+            queue.push(i.into());
+        }
+        assert_eq!(allocs(), 1);
+        queue.push((BLOCK_SIZE/2).into());
+        assert_eq!(allocs(), 2);
+        
+        // Block boundary does not allocate.
+        for i in BLOCK_SIZE/2+1..BLOCK_SIZE+1 {
+            queue.push(i.into());
+        }
+        assert!(!queue.is_single_block());
+        assert_eq!(allocs(), 2);
+        
+        for i in 0..BLOCK_SIZE+1 {
+            assert_eq!(usize::from(reader.next().unwrap().clone()), i);
+        }
+        assert!(reader.next().is_none());
+        
+        drop((queue, reader));
+        assert_eq!(allocator.deallocs.load(Ordering::Relaxed), 2);
+    }
+    
+    #[test]
+    fn on_first_overflow_test() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        
+        let calls = Arc::new(AtomicUsize::new(0));
+        let mut queue: Queue<usize> = Queue::new();
+        queue.on_first_overflow({
+            let calls = calls.clone();
+            move || { calls.fetch_add(1, Ordering::Relaxed); }
+        });
+        for i in 0..BLOCK_SIZE {
+            queue.push(i);
+        }
+        assert_eq!(calls.load(Ordering::Relaxed), 0);
+        for i in 0..BLOCK_SIZE*2 {
+            queue.push(i);
+        }
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+    }
+    
+    #[test]
+    fn push_unwind_test() {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+        use std::sync::PoisonError;
+        
+        let value = Arc::new(0);
+        let queue: Arc<Mutex<Queue<Arc<usize>>>> = Default::default();
+        let mut reader = queue.lock().unwrap().reader();
+        queue.lock().unwrap().on_first_overflow(|| panic!("injected"));
+        for _ in 0..BLOCK_SIZE {
+            queue.lock().unwrap().push(value.clone());
+        }
+        
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            queue.lock().unwrap().push(value.clone());
+        }));
+        assert!(result.is_err());
+        // Value dropped, not published.
+        assert_eq!(Arc::strong_count(&value), BLOCK_SIZE+1);
+        for _ in 0..BLOCK_SIZE {
+            assert!(reader.next().is_some());
+        }
+        assert!(reader.next().is_none());
+        
+        // Usable after poison.
+        let mut queue = queue.lock().unwrap_or_else(PoisonError::into_inner);
+        queue.push(Arc::new(1));
+        queue.push(Arc::new(2));
+        assert_eq!(reader.next().map(|v| **v), Some(1));
+        assert_eq!(reader.next().map(|v| **v), Some(2));
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn extend_test() {
+        let mut queue: Queue<usize, 64> = Queue::default();
+        let mut reader = queue.reader();
+        queue.extend(0..64*3);
+        queue.extend(std::iter::empty());
+        queue.push(64*3);
+        assert!(std::iter::from_fn(|| reader.next().copied()).eq(0..=64*3));
+    }
+
+    #[test]
+    fn writer_test() {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+        
+        let mut queue: Queue<usize, 64> = Queue::default().with_rollover_threshold(0.5);
+        let mut reader = queue.reader();
+        queue.push(0);
+        let mut writer = queue.writer();
+        for i in 1..64*3 {
+            writer.push(i);
+        }
+        drop(writer);
+        queue.push(64*3);
+        assert!(std::iter::from_fn(|| reader.next().copied()).eq(0..=64*3));
+        
+        // Panic in block insert.
+        let mut queue: Queue<usize, 64> = Queue::default();
+        let mut reader = queue.reader();
+        queue.on_first_overflow(|| panic!("injected"));
+        let mut writer = queue.writer();
+        for i in 0..64 {
+            writer.push(i);
+        }
+        let result = catch_unwind(AssertUnwindSafe(|| writer.push(1000)));
+        assert!(result.is_err());
+        writer.push(64);
+        writer.push(65);
+        drop(writer);
+        assert!(std::iter::from_fn(|| reader.next().copied()).eq(0..66));
+    }
+
+    #[test]
+    fn clone_panic_test() {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        
+        /// Panics on `PANIC_ON`-th clone.
+        struct PanicOnClone(usize, Arc<AtomicUsize>);
+        const PANIC_ON: usize = 3;
+        impl Clone for PanicOnClone {
+            fn clone(&self) -> Self {
+                if self.1.fetch_add(1, Ordering::Relaxed) + 1 == PANIC_ON {
+                    panic!("injected");
+                }
+                Self(self.0, self.1.clone())
+            }
+        }
+        
+        let clones = Arc::new(AtomicUsize::new(0));
+        let mut queue: Queue<PanicOnClone, 64> = Queue::default();
+        let cloned  = queue.reader().cloned();
+        let copying = queue.reader().copying();
+        for i in 0..64+10 {
+            queue.push(PanicOnClone(i, clones.clone()));
+        }
+        
+        fn check(mut reader: impl Iterator<Item = PanicOnClone>, clones: &AtomicUsize) -> bool {
+            clones.store(0, Ordering::Relaxed);
+            assert_eq!(reader.next().unwrap().0, 0);
+            assert_eq!(reader.next().unwrap().0, 1);
+            let result = catch_unwind(AssertUnwindSafe(|| reader.next()));
+            assert!(result.is_err());
+            reader.map(|value| value.0).eq(2..64+10)
+        }
+        // Message, that failed to clone, is consumed.
+        assert!(!check(cloned, &clones));
+        // Message, that failed to clone, is not lost.
+        assert!(check(copying, &clones));
+    }
+
+    #[test]
+    fn copying_reader_test() {
+        let mut queue: Queue<usize> = Queue::new();
+        let mut reader = queue.reader().copying();
+        for i in 0..BLOCK_SIZE+1 {
+            queue.push(i);
+        }
+        assert!(reader.by_ref().take(BLOCK_SIZE).eq(0..BLOCK_SIZE));
+        
+        // Moved to the next block, after reading the last message of the first one.
+        let reader = reader.into_inner();
+        let mut blocks = 0;
+        reader.for_each_block(|_| blocks += 1);
+        assert_eq!(blocks, 1);
+        assert!(reader.copying().eq([BLOCK_SIZE]));
+    }
+
+    #[test]
+    fn into_drain_test() {
+        const COUNT: usize = BLOCK_SIZE * 2 + 10;
+        let mut queue: Queue<StringWrapper> = Queue::new();
+        let mut reader1 = queue.reader();
+        let mut reader2 = queue.reader();
+        for i in 0..COUNT {
+            queue.push(i.into());
+        }
+        reader1.next();
+        for _ in 0..BLOCK_SIZE+5 {
+            reader2.next();
+        }
+        
+        // Last block is shared with queue.
+        let drained: Vec<usize> = reader2.clone().into_drain().map(Into::into).collect();
+        assert!(drained.is_empty());
+        drop(queue);
+        
+        // Blocks after reader2 are shared with reader1.
+        assert_eq!(reader2.clone().into_drain().count(), 0);
+        
+        // Take part, and drop the rest in place.
+        let mut drain = reader1.into_drain();
+        assert_eq!(usize::from(drain.next().unwrap()), 1);
+        drop(drain);
+        
+        let drained: Vec<usize> = reader2.into_drain().map(Into::into).collect();
+        assert!(drained.into_iter().eq(BLOCK_SIZE+5..COUNT));
+    }
+
+    #[test]
+    fn drain_all_test() {
+        let mut queues: Vec<Queue<StringWrapper>> = (0..3).map(|_| Queue::new()).collect();
+        let readers: Vec<_> = queues.iter().map(Queue::reader).collect();
+        for (i, queue) in queues.iter_mut().enumerate() {
+            for j in 0..BLOCK_SIZE+10 {
+                queue.push((i*100_000 + j).into());
+            }
+        }
+        drop(queues);
+        
+        let drained: Vec<usize> = Reader::drain_all(readers).map(Into::into).collect();
+        let expected: Vec<usize> = (0..3)
+            .flat_map(|i| (0..BLOCK_SIZE+10).map(move |j| i*100_000 + j))
+            .collect();
+        assert_eq!(drained, expected);
+    }
+
+    #[test]
+    fn reader_rewindable_test() {
+        let mut queue: Queue<usize> = Queue::new();
+        queue.push(100);
+        let mut reader = queue.reader_rewindable();
+        for i in 0..BLOCK_SIZE+5 {
+            queue.push(i);
+        }
+        for _ in 0..2 {
+            assert!(std::iter::from_fn(|| reader.next().copied()).eq(0..BLOCK_SIZE+5));
+            // Origin does not hold back quiesce.
+            queue.quiesce();
+            reader.rewind_to_subscription();
+        }
+    }
+
+    #[test]
+    fn subscribe_test() {
+        let mut queue: Queue<usize> = Queue::new();
+        let mut subscribers = std::collections::HashMap::new();
+        for _ in 0..3 {
+            let subscription = queue.subscribe();
+            subscribers.insert(subscription.id(), subscription);
+        }
+        assert_eq!(subscribers.len(), 3);
+        assert_eq!(queue.subscriber_count(), 3);
+        
+        queue.push(1);
+        for subscription in subscribers.values_mut() {
+            assert_eq!(subscription.next(), Some(&1));
+        }
+        
+        let id = *subscribers.keys().next().unwrap();
+        let subscription = subscribers.remove(&id).unwrap();
+        let subscription = std::thread::spawn(move || subscription).join().unwrap();
+        assert_eq!(queue.subscriber_count(), 3);
+        drop(subscription);
+        assert_eq!(queue.subscriber_count(), 2);
+        subscribers.clear();
+        assert_eq!(queue.subscriber_count(), 0);
+    }
+    
+    #[test]
+    fn take_full_block_test() {
+        let mut queue: Queue<StringWrapper> = Queue::new();
+        for i in 0..BLOCK_SIZE-1 {
+            queue.push(i.into());
+        }
+        assert!(queue.take_full_block().is_none());
+        queue.push((BLOCK_SIZE-1).into());
+        
+        // Reachable by reader.
+        let reader = queue.reader();
+        assert!(queue.take_full_block().is_none());
+        drop(reader);
+        
+        let block = queue.take_full_block().unwrap();
+        assert_eq!(block.len(), BLOCK_SIZE);
+        assert!(block.map(usize::from).eq(0..BLOCK_SIZE));
+        
+        // Queue continues.
+        let mut reader = queue.reader();
+        for i in 0..BLOCK_SIZE+1 {
+            queue.push(i.into());
+        }
+        assert!(queue.take_full_block().is_none());
+        assert_eq!(reader.next().map(|v| usize::from(v.clone())), Some(0));
+        drop(reader);
+        
+        // Only the last block.
+        for i in BLOCK_SIZE+1..BLOCK_SIZE*2 {
+            queue.push(i.into());
+        }
+        // Partially consumed.
+        let mut block = queue.take_full_block().unwrap();
+        assert_eq!(block.next().map(usize::from), Some(BLOCK_SIZE));
+        drop(block);
+        drop(queue);
+    }
+    
+    #[test]
+    fn len_test() {
+        let mut queue: Queue<usize> = Queue::new();
+        queue.push(0);
+        // No readers - nothing is held.
+        assert!(queue.is_empty());
+        
+        let mut reader1 = queue.reader();
+        let mut reader2 = queue.reader();
+        for i in 0..BLOCK_SIZE+10 {
+            queue.push(i);
+        }
+        assert_eq!(queue.approx_len(), BLOCK_SIZE+10);
+        while reader1.next().is_some() {}
+        assert_eq!(queue.approx_len(), BLOCK_SIZE+10);
+        while reader2.next().is_some() {}
+        assert!(queue.is_empty());
+        
+        queue.push(0);
+        assert_eq!(queue.approx_len(), 1);
+        
+        // Readers busy with a backlog, report on block switch.
+        for i in 0..BLOCK_SIZE*2 {
+            queue.push(i);
+        }
+        for _ in 0..BLOCK_SIZE {
+            reader1.next().unwrap();
+            reader2.next().unwrap();
+        }
+        // BLOCK_SIZE+1 messages are actually unread.
+        assert!(queue.approx_len() < BLOCK_SIZE*2 + 1);
+        
+        drop((reader1, reader2));
+        assert_eq!(queue.approx_len(), 0);
+    }
+    
+    #[test]
+    fn next_indexed_test() {
+        let mut queue: Queue<usize, 64> = Queue::default();
+        for i in 0..10 {
+            queue.push(i);
+        }
+        let mut reader = queue.reader();
+        let mut clone = reader.clone();
+        for i in 10..64*2 {
+            queue.push(i);
+        }
+        for i in 10..64*2 {
+            assert_eq!(reader.next_indexed(), Some((i as u64, &i)));
+        }
+        assert_eq!(reader.next_indexed(), None);
+        
+        // Same index for every reader.
+        clone.next();
+        assert_eq!(clone.offset().0, 11);
+        assert_eq!(clone.next_indexed(), Some((11, &11)));
+    }
+    
+    #[test]
+    fn skip_to_tail_test() {
+        let mut queue: Queue<usize> = Queue::new();
+        let mut reader = queue.reader();
+        for i in 0..BLOCK_SIZE*2+10 {
+            queue.push(i);
+        }
+        assert_eq!(reader.next(), Some(&0));
+        reader.skip_to_tail();
+        assert_eq!(reader.lag(), 0);
+        assert_eq!(reader.next(), None);
+        
+        // At the block end.
+        for i in 0..BLOCK_SIZE-10 {
+            queue.push(i);
+        }
+        reader.skip_to_tail();
+        assert_eq!(reader.next(), None);
+        queue.push(1000);
+        assert_eq!(reader.next(), Some(&1000));
+        assert_eq!(reader.next(), None);
+    }
+    
+    #[test]
+    fn is_closed_and_drained_test() {
+        let mut queue: Queue<usize> = Queue::new();
+        let mut reader = queue.reader();
+        for i in 0..BLOCK_SIZE+10 {
+            queue.push(i);
+        }
+        assert!(!reader.is_closed_and_drained());
+        queue.close();
+        assert!(queue.is_closed());
+        
+        let mut sum = 0;
+        while !reader.is_closed_and_drained() {
+            sum += reader.next().unwrap();
+        }
+        assert_eq!(sum, (0..BLOCK_SIZE+10).sum());
+        assert_eq!(reader.next(), None);
+        
+        // Dropped queue is closed too.
+        let mut queue: Queue<usize> = Queue::new();
+        let reader = queue.reader();
+        queue.push(1);
+        drop(queue);
+        assert!(!reader.is_closed_and_drained());
+    }
+    
+    #[test]
+    fn lag_test() {
+        let mut queue: Queue<usize> = Queue::new();
+        let mut reader = queue.reader();
+        assert_eq!(reader.lag(), 0);
+        for i in 0..BLOCK_SIZE*2+10 {
+            queue.push(i);
+        }
+        assert_eq!(reader.lag(), BLOCK_SIZE*2+10);
+        for _ in 0..BLOCK_SIZE+5 {
+            reader.next();
+        }
+        assert_eq!(reader.lag(), BLOCK_SIZE+5);
+        while reader.next().is_some() {}
+        assert_eq!(reader.lag(), 0);
+    }
+    
+    #[test]
+    fn max_blocks_test() {
+        let mut queue: Queue<usize, 64> = Queue::default().with_max_blocks(2);
+        let mut slow = queue.reader();
+        let mut fast = queue.reader();
+        let mut fast_sum: usize = 0;
+        for i in 0..64*5 {
+            queue.push(i);
+            fast_sum += fast.next().unwrap();
+        }
+        assert_eq!(fast_sum, (0..64*5).sum());
+        assert_eq!(fast.skipped(), 0);
+        
+        // Queue keeps blocks 3 and 4.
+        let mut values = Vec::new();
+        while let Some(value) = slow.next() {
+            values.push(*value);
+        }
+        assert_eq!(values.len(), 64*3);
+        assert_eq!(values[63], 63);
+        assert_eq!(values[64], 64*3);
+        assert_eq!(slow.skipped(), 64*2);
+        
+        // Not lagging anymore.
+        queue.push(1000);
+        assert_eq!(slow.next(), Some(&1000));
+        assert_eq!(slow.skipped(), 64*2);
+    }
+    
+    #[test]
+    fn max_blocks_block_switch_test() {
+        let mut queue: Queue<usize, 64> = Queue::default().with_max_blocks(2);
+        let mut by_block = queue.reader();
+        let mut filtered = queue.reader().filter_blocks(|_| true);
+        let seqs: Arc<Mutex<Vec<u64>>> = Default::default();
+        filtered.reader.on_block_advance({
+            let seqs = seqs.clone();
+            move |seq| seqs.lock().unwrap().push(seq)
+        });
+        for i in 0..64*5 {
+            queue.push(i);
+        }
+        
+        // Queue keeps blocks 3 and 4.
+        assert_eq!(by_block.next_block().unwrap()[0], 0);
+        assert_eq!(by_block.next_block().unwrap()[0], 64*3);
+        assert_eq!(by_block.skipped(), 64*2);
+        
+        let mut values = Vec::new();
+        while let Some(value) = filtered.next() {
+            values.push(*value);
+        }
+        assert!(values.into_iter().eq((0..64).chain(64*3..64*5)));
+        assert_eq!(filtered.reader.skipped(), 64*2);
+        assert_eq!(*seqs.lock().unwrap(), [1, 2, 3, 4]);
+    }
+    
+    #[test]
+    fn readers_lag_test() {
+        let mut queue: Queue<usize> = Queue::new();
+        let mut fast = queue.subscribe();
+        let slow = queue.subscribe();
+        // Not registered.
+        let _reader = queue.reader();
+        for i in 0..BLOCK_SIZE+10 {
+            queue.push(i);
+        }
+        while fast.next().is_some() {}
+        assert_eq!(queue.readers_lag(), vec![(fast.id(), 0), (slow.id(), BLOCK_SIZE+10)]);
+        
+        drop(slow);
+        queue.push(0);
+        assert_eq!(queue.readers_lag(), vec![(fast.id(), 1)]);
+    }
+
+    #[test]
+    fn next_block_test() {
+        let mut queue: Queue<usize> = Queue::new();
+        let mut reader = queue.reader();
+        for i in 0..BLOCK_SIZE*2 + 10 {
+            queue.push(i);
+        }
+        assert!(reader.next_block().unwrap().iter().copied().eq(0..BLOCK_SIZE));
+        assert_eq!(reader.next(), Some(&BLOCK_SIZE));
+        // Middle of block.
+        assert_eq!(reader.next_block(), None);
+        for i in BLOCK_SIZE+1..BLOCK_SIZE*2 {
+            assert_eq!(reader.next(), Some(&i));
+        }
+        // Not full.
+        assert_eq!(reader.next_block(), None);
+        assert_eq!(reader.next(), Some(&(BLOCK_SIZE*2)));
+    }
+
+    #[test]
+    fn push_slice_test() {
+        fn test<const N: usize>(queue: Queue<usize, N>) {
+            let mut queue = queue;
+            let mut expected_queue: Queue<usize, N> = Queue::default();
+            let mut reader = queue.reader();
+            let mut expected_reader = expected_queue.reader();
+            
+            let values: Vec<usize> = (0..N*3 + 10).collect();
+            queue.push(0);
+            expected_queue.push(0);
+            // Spans several blocks.
+            for chunk in [&values[..5], &values[5..N*2 + 7], &[], &values[N*2 + 7..]] {
+                queue.push_slice(chunk);
+                for &value in chunk {
+                    expected_queue.push(value);
+                }
+            }
+            assert!(
+                std::iter::from_fn(|| reader.next().copied())
+                .eq(std::iter::from_fn(|| expected_reader.next().copied()))
+            );
+            assert_eq!(queue.last_block.seq, expected_queue.last_block.seq);
+        }
+        test::<64>(Default::default());
+        test::<BLOCK_SIZE>(Default::default());
+        test::<64>(Queue::default().with_rollover_threshold(0.5));
+    }
+
+    #[test]
+    fn next_guard_test() {
+        let mut queue: Queue<StringWrapper> = Queue::new();
+        let mut reader = queue.reader();
+        for i in 0..BLOCK_SIZE*2 {
+            queue.push(i.into());
+        }
+        let first = reader.next_guard().unwrap();
+        let guards: Vec<_> = std::iter::from_fn(|| reader.next_guard()).collect();
+        drop((queue, reader));
+        
+        // Guards outlive reader, queue, and blocks they were read from.
+        assert_eq!(usize::from((*first).clone()), 0);
+        assert!(guards.iter().map(|guard| usize::from((**guard).clone())).eq(1..BLOCK_SIZE*2));
+    }
+
+    #[test]
+    fn next_chunk_test() {
+        let mut queue: Queue<usize> = Queue::new();
+        let mut reader = queue.reader();
+        assert_eq!(reader.next_chunk(), None);
+        for i in 0..BLOCK_SIZE+10 {
+            queue.push(i);
+        }
+        assert_eq!(reader.next(), Some(&0));
+        // Stops at block end.
+        assert!(reader.next_chunk().unwrap().iter().copied().eq(1..BLOCK_SIZE));
+        assert!(reader.next_chunk().unwrap().iter().copied().eq(BLOCK_SIZE..BLOCK_SIZE+10));
+        assert_eq!(reader.next_chunk(), None);
+        
+        queue.push(1000);
+        assert_eq!(reader.next_chunk(), Some(&[1000][..]));
+        assert_eq!(reader.next(), None);
+    }
+
+    #[test]
+    fn on_block_advance_test() {
+        let mut queue: Queue<usize> = Queue::new();
+        let mut reader = queue.reader();
+        let seqs: Arc<Mutex<Vec<u64>>> = Default::default();
+        reader.on_block_advance({
+            let seqs = seqs.clone();
+            move |seq| seqs.lock().unwrap().push(seq)
+        });
+        for i in 0..BLOCK_SIZE*2 + 1 {
+            queue.push(i);
+        }
+        let mut clone = reader.clone();
+        while reader.next().is_some() {}
+        assert_eq!(*seqs.lock().unwrap(), [1, 2]);
+        
+        // Clones do not inherit callback.
+        while clone.next().is_some() {}
+        assert_eq!(*seqs.lock().unwrap(), [1, 2]);
+    }
+
+    #[test]
+    fn catch_up_count_test() {
+        let mut queue: Queue<usize> = Queue::new();
+        let mut reader = queue.reader();
+        assert_eq!(reader.catch_up_count(&queue), 0);
+        
+        for i in 0..BLOCK_SIZE+10 {
+            queue.push(i);
+        }
+        assert_eq!(reader.catch_up_count(&queue), BLOCK_SIZE+10);
+        reader.next();
+        assert_eq!(reader.catch_up_count(&queue), BLOCK_SIZE+9);
+        while reader.next().is_some() {}
+        assert_eq!(reader.catch_up_count(&queue), 0);
+    }
+
+    #[test]
+    fn zeroize_test() {
+        use std::sync::atomic::Ordering;
+        use crate::test::ScanningAllocator;
+        
+        let mut queue: Queue<StringWrapper> = Queue::new().with_zeroize(true);
+        let mut reader = queue.reader();
+        for i in 0..BLOCK_SIZE+1 {
+            queue.push(i.into());
+        }
+        for i in 0..BLOCK_SIZE+1 {
+            assert_eq!(usize::from(reader.next().unwrap().clone()), i);
+        }
+        
+        // Freed blocks do not contain messages.
+        const VALUE: [u8; 32] = [ScanningAllocator::PATTERN; 32];
+        for zeroize in [true, false] {
+            let allocator: Arc<ScanningAllocator> = Default::default();
+            let mut queue: Queue<[u8; 32], 64> = Queue::default()
+                .with_block_allocator(allocator.clone())
+                .with_zeroize(zeroize);
+            let reader = queue.reader();
+            for _ in 0..64*3 {
+                queue.push(VALUE);
+            }
+            drop(reader);
+            drop(queue);
+            assert!(allocator.deallocs.load(Ordering::Relaxed) >= 3);
+            let dirty = allocator.dirty.load(Ordering::Relaxed);
+            assert_eq!(dirty == 0, zeroize);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn prefault_test() {
+        let mut queue: Queue<[u8; 1024]> = Queue::new().with_prefault(true).with_adaptive_blocks();
+        let mut reader = queue.reader();
+        for i in 0..BLOCK_SIZE*2 {
+            queue.push([i as u8; 1024]);
+        }
+        for i in 0..BLOCK_SIZE*2 {
+            assert_eq!(reader.next(), Some(&[i as u8; 1024]));
+        }
+    }
+    
+    #[test]
+    fn prefault_keeps_messages_test() {
+        let mut queue: Queue<u32> = Queue::new();
+        let mut reader = queue.reader();
+        queue.push(0xDEADBEEF);
+        let mut queue = queue.with_prefault(true);
+        queue.push(1);
+        assert_eq!(reader.next(), Some(&0xDEADBEEF));
+        assert_eq!(reader.next(), Some(&1));
+    }
+
+    #[test]
+    fn advance_to_test() {
+        let mut queue: Queue<usize> = Queue::new();
+        let mut reader1 = queue.reader();
+        for i in 0..BLOCK_SIZE*2 {
+            queue.push(i);
+        }
+        let mut reader2 = reader1.clone();
+        for _ in 0..BLOCK_SIZE+10 {
+            reader2.next();
+        }
+        
+        reader2.advance_to(&reader1);
+        assert_eq!(reader2.next(), Some(&(BLOCK_SIZE+10)));
+        
+        reader1.advance_to(&reader2);
+        assert_eq!(reader1.next(), Some(&(BLOCK_SIZE+11)));
+    }
+    
+    #[test]
+    #[should_panic]
+    fn advance_to_other_queue_test() {
+        let mut queue1: Queue<usize> = Queue::new();
+        let queue2: Queue<usize> = Queue::new();
+        let reader1 = queue1.reader();
+        let mut reader2 = queue2.reader();
+        queue1.push(1);
+        let mut reader1_clone = reader1.clone();
+        reader1_clone.next();
+        reader2.advance_to(&reader1_clone);
+    }
+
+    #[test]
+    fn try_advance_to_other_queue_test() {
+        let mut queue1: Queue<usize> = Queue::new();
+        let queue2: Queue<usize> = Queue::new();
+        let mut reader1 = queue1.reader();
+        let mut reader2 = queue2.reader();
+        queue1.push(1);
+        reader1.next();
+        assert_eq!(reader2.try_advance_to(&reader1), Err(crate::Error::DifferentQueues));
+        assert_eq!(reader1.try_advance_to(&reader2), Err(crate::Error::DifferentQueues));
     }
-}
-
-
-#[cfg(test)]
-mod test{
-    use std::sync::Arc;
-    use std::sync::atomic::{AtomicUsize, Ordering};
-    use rand::{Rng, SeedableRng};
-    use crate::block::BLOCK_SIZE;
-    use crate::spmc::Queue;
-    use crate::LendingReader;
-    use crate::test::StringWrapper;
 
     fn test_spmc_mt<Value>(rt: usize, len: usize)
     where
@@ -158,7 +2757,7 @@ mod test{
         let mut joins = Vec::new();
         
         // Readers
-        let control_sum = (0..len).sum();        
+        let control_sum: usize = (0..len).sum();
         for _ in 0..rt { 
             let mut reader = queue.lock().reader();
             joins.push(std::thread::spawn(move || {
@@ -0,0 +1,215 @@
+//! Single-producer, single-consumer.
+//!
+//! Same block design as [spmc], but without multi-reader machinery.
+//!
+//! [spmc]: crate::spmc
+
+use crate::sync::Ordering;
+use core::marker::PhantomData;
+use core::pin::Pin;
+use branch_hints::unlikely;
+use crate::block::{Block, BlockArc, BLOCK_SIZE};
+use crate::LendingReader;
+
+/// Constructs a new queue, returning its two ends.
+///
+/// [Consumer] will receive all messages pushed by [Producer].
+#[must_use]
+#[inline]
+pub fn queue<T>() -> (Producer<T>, Consumer<T>) {
+    const { Block::<T>::check_capacity(BLOCK_SIZE) };
+    let block = Block::new(BLOCK_SIZE);
+    let consumer = Consumer{
+        block: block.clone(),
+        index: 0,
+        len  : 0,
+        phantom_data: PhantomData,
+    };
+    (Producer{last_block: block, phantom_data: PhantomData}, consumer)
+}
+
+/// Queue producer.
+///
+/// Producer of non-[Send] messages is not [Send]:
+/// ```compile_fail
+/// let (mut producer, _consumer) = chute::spsc::queue();
+/// producer.push(std::rc::Rc::new(1));
+/// std::thread::spawn(move || producer.push(std::rc::Rc::new(2)));
+/// ```
+///
+/// Constructed by [queue()].
+pub struct Producer<T>{
+    last_block: BlockArc<T>,
+    /// Not [Send] by itself - see impl below.
+    phantom_data: PhantomData<*const T>,
+}
+
+// Producer moves `T`s to the consumer's thread.
+unsafe impl<T: Send> Send for Producer<T>{}
+
+impl<T> Producer<T> {
+    /// Overwrite freed block memory with zeros, after dropping messages.
+    /// 
+    /// Use it for sensitive data, that should not linger in freed heap.
+    /// Off by default. Applies to the current block and all the following.
+    #[must_use]
+    #[inline]
+    pub fn with_zeroize(self, zeroize: bool) -> Self {
+        self.last_block.zeroize.store(zeroize, Ordering::Relaxed);
+        self
+    }
+    
+    #[cold]
+    #[inline(never)]
+    fn insert_block(&mut self) {
+        // 1. Make new block
+        //    +1 counter for Producer::last_block
+        //    +1 counter for Block::next
+        let mut new_block = self.last_block.new_next(2, BLOCK_SIZE);
+
+        // 2. Connect new block with old
+        self.last_block.next.store(new_block.as_non_null().as_ptr(), Ordering::Release);
+
+        // 3. Set new block
+        self.last_block = new_block;
+    }
+
+    #[inline]
+    pub fn push(&mut self, value: T) {
+        let mut len = self.last_block.len.load(Ordering::Relaxed);
+        if unlikely(len == self.last_block.capacity) {
+            self.insert_block();
+            len = 0;
+        }
+
+        // See spmc::Queue::push.
+        unsafe{
+            let mem = self.last_block.mem();
+            mem.add(len).write(value);
+        }
+
+        self.last_block.publish_len(len+1);
+    }
+}
+
+/// Queue consumer.
+///
+/// Consumer of non-[Send] messages is not [Send]:
+/// ```compile_fail
+/// # use chute::LendingReader;
+/// let (mut producer, mut consumer) = chute::spsc::queue();
+/// producer.push(std::rc::Rc::new(1));
+/// std::thread::spawn(move || consumer.next().cloned());
+/// ```
+///
+/// Constructed by [queue()].
+pub struct Consumer<T>{
+    block: BlockArc<T>,
+    index: usize,
+    len  : usize,
+    /// Not [Send] by itself - see impl below.
+    phantom_data: PhantomData<*const T>,
+}
+
+// Consumer takes `T`s from the producer's thread, and may drop them.
+unsafe impl<T: Send> Send for Consumer<T>{}
+
+impl<T> Consumer<T> {
+    /// Same as [next()], but returns pinned reference.
+    /// 
+    /// Messages never move after being pushed, see [pinning].
+    /// 
+    /// [next()]: LendingReader::next
+    /// [pinning]: crate#pinning
+    #[inline]
+    pub fn next_pinned(&mut self) -> Option<Pin<&T>> {
+        // Values stay in place until dropped in place with their block.
+        self.next().map(|value| unsafe{ Pin::new_unchecked(value) })
+    }
+}
+
+impl<T> LendingReader for Consumer<T>{
+    type Item = T;
+
+    #[inline]
+    fn next(&mut self) -> Option<&T> {
+        if self.index == self.len {
+            if unlikely(self.len == self.block.capacity) {
+                // fetch next block, release current
+                if let Some(next_block) = self.block.try_load_next(Ordering::Acquire) {
+                    self.index = 0;
+                    self.len   = next_block.load_len();
+                    self.block = next_block;
+
+                    if self.len == 0 {
+                        return None;
+                    }
+                } else {
+                    return None;
+                }
+            } else {
+                // Reread len. See spmc::Reader::next.
+                let block_len = self.block.load_len();
+                if self.len == block_len {
+                    // nothing changed.
+                    return None;
+                }
+                self.len = block_len;
+            }
+        }
+
+        unsafe{
+            let value = &*self.block.mem().add(self.index);
+            self.index += 1;
+            Some(value)
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod test{
+    use rand::{Rng, SeedableRng};
+    use crate::block::BLOCK_SIZE;
+    use crate::spsc;
+    use crate::LendingReader;
+    use crate::test::StringWrapper;
+
+    fn test_spsc_mt<Value>(len: usize)
+    where
+        Value: From<usize> + Into<usize> + Clone + Send + 'static,
+    {
+        let (mut producer, mut consumer) = spsc::queue::<Value>();
+
+        let reader = std::thread::spawn(move || {
+            let mut i = 0;
+            while i != len {
+                if let Some(value) = consumer.next() {
+                    assert_eq!(value.clone().into(), i);
+                    i += 1;
+                }
+            }
+        });
+        let writer = std::thread::spawn(move || {
+            for i in 0..len {
+                producer.push(i.into());
+            }
+        });
+
+        reader.join().unwrap();
+        writer.join().unwrap();
+    }
+
+    #[test]
+    fn fuzzy_spsc(){
+        const RANGE  : usize = if cfg!(miri) {8 } else {40 } * BLOCK_SIZE;
+        const REPEATS: usize = if cfg!(miri) {10} else {100};
+
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0xe15bb9db3dee3a0f);
+        for _ in 0..REPEATS {
+            let len = rng.gen_range(0..RANGE);
+            test_spsc_mt::<usize>(len);
+            test_spsc_mt::<StringWrapper>(len);
+        }
+    }
+}
@@ -0,0 +1,24 @@
+/// Reader as async [Stream] of cloned messages.
+/// 
+/// Pending stream is woken by pushes, that wake blocked readers - 
+/// see `Reader::wait()`. Ends, when queue is closed and everything is read -
+/// see `Reader::try_next()`.
+/// 
+/// Constructed by [spmc::Reader::into_stream()] and [mpmc::Reader::into_stream()].
+/// 
+/// [Stream]: futures_core::Stream
+/// [spmc::Reader::into_stream()]: crate::spmc::Reader::into_stream
+/// [mpmc::Reader::into_stream()]: crate::mpmc::Reader::into_stream
+pub struct ReaderStream<R>{
+    pub(crate) reader: R,
+}
+
+impl<R> ReaderStream<R>{
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+// Reader is never pinned - messages are cloned out.
+impl<R> Unpin for ReaderStream<R>{}
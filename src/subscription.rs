@@ -0,0 +1,68 @@
+use core::ops::{Deref, DerefMut};
+use alloc::sync::Arc;
+use crate::LendingReader;
+use crate::subscriptions::Subscriptions;
+
+/// Reader with tracked lifetime.
+/// 
+/// Counted in `Queue::subscriber_count()` until dropped. Each subscription
+/// has an [id()], unique within its queue - use it to key subscribers in
+/// a collection.
+/// 
+/// Derefs to the underlying reader.
+/// 
+/// Constructed by [spmc::Queue::subscribe()] and [mpmc::Queue::subscribe()].
+/// 
+/// [id()]: Self::id
+/// [spmc::Queue::subscribe()]: crate::spmc::Queue::subscribe
+/// [mpmc::Queue::subscribe()]: crate::mpmc::Queue::subscribe
+pub struct Subscription<R>{
+    reader: R,
+    id: u64,
+    subscriptions: Arc<Subscriptions>,
+}
+
+impl<R> Subscription<R>{
+    #[inline]
+    /// `id` - from [Subscriptions::add_subscriber()].
+    pub(crate) fn new(reader: R, id: u64, subscriptions: Arc<Subscriptions>) -> Self {
+        Self{reader, id, subscriptions}
+    }
+    
+    #[inline]
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+}
+
+impl<R> Deref for Subscription<R>{
+    type Target = R;
+
+    #[inline]
+    fn deref(&self) -> &R {
+        &self.reader
+    }
+}
+
+impl<R> DerefMut for Subscription<R>{
+    #[inline]
+    fn deref_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+}
+
+impl<R: LendingReader> LendingReader for Subscription<R>{
+    type Item = R::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<&Self::Item> {
+        self.reader.next()
+    }
+}
+
+impl<R> Drop for Subscription<R>{
+    #[inline]
+    fn drop(&mut self) {
+        self.subscriptions.remove_subscriber(self.id);
+    }
+}
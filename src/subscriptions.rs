@@ -0,0 +1,337 @@
+use alloc::boxed::Box;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::ops::Deref;
+use core::ptr::{self, NonNull};
+use crate::sync::Mutex;
+use crate::sync::{AtomicBool, AtomicPtr, AtomicU64, AtomicUsize, Ordering};
+use crate::notify::Notifier;
+
+/// Queue's reader registry. 
+/// 
+/// Shared between queue and its readers.
+#[derive(Default)]
+pub(crate) struct Subscriptions {
+    /// Total number of readers ever constructed by `Queue::reader()`.
+    count  : AtomicUsize,
+    /// Notified on `count` change.
+    count_notifier: Notifier,
+
+    /// All readers, including clones.
+    readers: Registry,
+    /// Notified when reader reports position, or is dropped.
+    pub position_notifier: Notifier,
+    /// Number of live readers, including clones. Diagnostics only.
+    reader_count: AtomicUsize,
+    /// Number of live `mpmc::Writer`s. Diagnostics only.
+    writer_count: AtomicUsize,
+    
+    /// Notified on new messages.
+    pub message_notifier: Notifier,
+    /// Set by `Queue::close()`. Notified with `message_notifier`.
+    closed: AtomicBool,
+    
+    /// Start of the oldest block, readers are allowed to be at.
+    /// See `spmc::Queue::with_max_blocks()`.
+    oldest_position: AtomicUsize,
+    
+    /// Number of live [Subscription]s.
+    /// 
+    /// [Subscription]: crate::Subscription
+    subscribers: AtomicUsize,
+    next_subscriber_id: AtomicU64,
+    /// Reader slots of live [Subscription]s, by id.
+    /// 
+    /// [Subscription]: crate::Subscription
+    subscriber_slots: Mutex<Vec<(u64, SlotPtr)>>,
+}
+
+impl Subscriptions {
+    /// Register reader constructed by `Queue::reader()`.
+    #[inline]
+    pub fn subscribe(self: &Arc<Self>, position: usize) -> ReaderState {
+        let state = self.register(position);
+
+        self.count.fetch_add(1, Ordering::SeqCst);
+        self.count_notifier.notify();
+
+        state
+    }
+
+    /// Register reader without counting it as subscription.
+    /// 
+    /// Does not allocate, if there is a slot of dropped reader to reuse.
+    #[inline]
+    pub fn register(self: &Arc<Self>, position: usize) -> ReaderState {
+        let slot = self.readers.acquire(position);
+        self.reader_count.fetch_add(1, Ordering::Relaxed);
+
+        ReaderState{
+            slot,
+            subscriptions: self.clone(),
+        }
+    }
+
+    /// Marks queue closed, and wakes blocked readers.
+    #[inline]
+    pub fn close(&self) {
+        // SeqCst - as messages, for Notifier.
+        self.closed.store(true, Ordering::SeqCst);
+        self.message_notifier.notify();
+    }
+    
+    #[inline]
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::SeqCst)
+    }
+
+    /// Readers behind `position` should skip to it.
+    #[inline]
+    pub fn set_oldest_position(&self, position: usize) {
+        self.oldest_position.store(position, Ordering::Release);
+    }
+    
+    #[inline]
+    pub fn oldest_position(&self) -> usize {
+        self.oldest_position.load(Ordering::Acquire)
+    }
+
+    #[inline]
+    pub fn count(&self) -> usize {
+        self.count.load(Ordering::SeqCst)
+    }
+    
+    #[inline]
+    pub fn reader_count(&self) -> usize {
+        self.reader_count.load(Ordering::Relaxed)
+    }
+    
+    #[inline]
+    pub fn writer_count(&self) -> usize {
+        self.writer_count.load(Ordering::Relaxed)
+    }
+    
+    #[inline]
+    pub fn add_writer(&self) {
+        self.writer_count.fetch_add(1, Ordering::Relaxed);
+    }
+    
+    #[inline]
+    pub fn remove_writer(&self) {
+        self.writer_count.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    /// Returns new subscriber id.
+    #[inline]
+    pub fn add_subscriber(&self, state: &ReaderState) -> u64 {
+        self.subscribers.fetch_add(1, Ordering::Relaxed);
+        let id = self.next_subscriber_id.fetch_add(1, Ordering::Relaxed);
+        self.subscriber_slots.lock()
+            .push((id, state.slot));
+        id
+    }
+    
+    #[inline]
+    pub fn remove_subscriber(&self, id: u64) {
+        self.subscribers.fetch_sub(1, Ordering::Relaxed);
+        let mut slots = self.subscriber_slots.lock();
+        if let Some(index) = slots.iter().position(|(slot_id, _)| *slot_id == id) {
+            slots.swap_remove(index);
+        }
+    }
+    
+    /// Last reported positions of live subscribers, by id.
+    pub fn subscriber_positions(&self) -> Vec<(u64, usize)> {
+        let mut positions: Vec<_> = 
+            self.subscriber_slots.lock()
+            .iter()
+            .map(|(id, slot)| (*id, slot.position.load(Ordering::SeqCst)))
+            .collect();
+        positions.sort_unstable_by_key(|(id, _)| *id);
+        positions
+    }
+    
+    #[inline]
+    pub fn subscriber_count(&self) -> usize {
+        self.subscribers.load(Ordering::Relaxed)
+    }
+
+    /// Blocks until [count()] reaches `target`.
+    ///
+    /// [count()]: Self::count
+    pub fn wait_count(&self, target: usize) {
+        if self.count() >= target {
+            return;
+        }
+        self.count_notifier.wait_while(|| self.count() < target);
+    }
+
+    /// Smallest position, reported by live readers. `usize::MAX` if none.
+    pub fn min_position(&self) -> usize {
+        self.readers.iter()
+            .filter_map(ReaderSlot::live_position)
+            .min()
+            .unwrap_or(usize::MAX)
+    }
+
+    /// Blocks until all live readers report position >= `position`.
+    ///
+    /// Readers registered after the call are waited for as well.
+    pub fn wait_readers(&self, position: usize) {
+        if self.min_position() >= position {
+            return;
+        }
+        self.position_notifier.wait_while(|| self.min_position() < position);
+    }
+    
+    /// Holds all locks - anyone who takes any of them blocks, while guard lives.
+    #[cfg(test)]
+    pub fn lock_all(&self) -> impl Sized + '_ {
+        (
+            self.count_notifier.lock(),
+            self.message_notifier.lock(),
+            self.position_notifier.lock(),
+        )
+    }
+}
+
+/// Reader slots - lock-free list, that only grows.
+/// 
+/// Slots of dropped readers are reused, so reader construction does
+/// not allocate once the registry has grown to the number of live readers.
+#[derive(Default)]
+struct Registry {
+    head: AtomicPtr<ReaderSlot>,
+}
+
+impl Registry {
+    fn acquire(&self, position: usize) -> SlotPtr {
+        for slot in self.iter() {
+            if !slot.in_use.load(Ordering::Relaxed) 
+                && slot.in_use.compare_exchange(false, true, Ordering::SeqCst, Ordering::Relaxed).is_ok()
+            {
+                slot.position.store(position, Ordering::SeqCst);
+                return SlotPtr(NonNull::from(slot));
+            }
+        }
+        
+        let slot = Box::into_raw(Box::new(ReaderSlot{
+            position: AtomicUsize::new(position),
+            in_use: AtomicBool::new(true),
+            next: ptr::null_mut(),
+        }));
+        let mut head = self.head.load(Ordering::Acquire);
+        loop {
+            // Not in the list yet - not shared.
+            unsafe{ (*slot).next = head; }
+            match self.head.compare_exchange_weak(head, slot, Ordering::SeqCst, Ordering::Acquire) {
+                Ok(_) => return SlotPtr(unsafe{ NonNull::new_unchecked(slot) }),
+                Err(new_head) => head = new_head,
+            }
+        }
+    }
+    
+    fn iter(&self) -> impl Iterator<Item = &ReaderSlot> {
+        let mut next = self.head.load(Ordering::Acquire);
+        core::iter::from_fn(move || {
+            // Slots are freed only with registry.
+            let slot = unsafe{ next.as_ref()? };
+            next = slot.next;
+            Some(slot)
+        })
+    }
+}
+
+impl Drop for Registry {
+    fn drop(&mut self) {
+        let mut next = self.head.load(Ordering::Acquire);
+        while !next.is_null() {
+            let slot = unsafe{ Box::from_raw(next) };
+            next = slot.next;
+        }
+    }
+}
+
+struct ReaderSlot {
+    /// Last reported reader position in queue.
+    ///
+    /// Updated when reader runs out of messages.
+    position: AtomicUsize,
+    in_use: AtomicBool,
+    /// Set once, before slot is put in the list.
+    next: *mut ReaderSlot,
+}
+
+impl ReaderSlot {
+    #[inline]
+    fn live_position(&self) -> Option<usize> {
+        // SeqCst - for `position_notifier`.
+        self.in_use.load(Ordering::SeqCst)
+            .then(|| self.position.load(Ordering::SeqCst))
+    }
+}
+
+/// Slot of [Registry]. Valid as long as registry.
+#[derive(Clone, Copy)]
+struct SlotPtr(NonNull<ReaderSlot>);
+
+// Slot has only atomics, besides immutable `next`.
+unsafe impl Send for SlotPtr {}
+unsafe impl Sync for SlotPtr {}
+
+impl Deref for SlotPtr {
+    type Target = ReaderSlot;
+
+    #[inline]
+    fn deref(&self) -> &ReaderSlot {
+        unsafe{ self.0.as_ref() }
+    }
+}
+
+/// Reader's state, shared with the queue.
+pub(crate) struct ReaderState {
+    /// In `subscriptions`'s registry, which lives as long as self.
+    slot: SlotPtr,
+    subscriptions: Arc<Subscriptions>,
+}
+
+impl ReaderState {
+    /// Free, if position did not change since the last report - so
+    /// readers can report on each empty poll.
+    #[inline]
+    pub fn report(&self, position: usize) {
+        // Only this reader writes its slot.
+        if self.slot.position.load(Ordering::Relaxed) != position {
+            self.store_position(position);
+        }
+    }
+    
+    #[cold]
+    #[inline(never)]
+    fn store_position(&self, position: usize) {
+        self.slot.position.store(position, Ordering::Release);
+        // Fences before `waiters` load.
+        self.subscriptions.position_notifier.notify();
+    }
+
+    #[inline]
+    pub fn subscriptions(&self) -> &Subscriptions {
+        &self.subscriptions
+    }
+
+    /// State for the reader clone.
+    #[inline]
+    pub fn register_clone(&self, position: usize) -> ReaderState {
+        self.subscriptions.register(position)
+    }
+}
+
+impl Drop for ReaderState {
+    #[inline]
+    fn drop(&mut self) {
+        self.slot.in_use.store(false, Ordering::SeqCst);
+        self.subscriptions.reader_count.fetch_sub(1, Ordering::Relaxed);
+        // Dropped reader does not hold back writers anymore.
+        self.subscriptions.position_notifier.notify();
+    }
+}
@@ -0,0 +1,62 @@
+//! Atomics of the lock-free core, and locks of the rest.
+//!
+//! With `loom` feature, atomics, [Mutex] and `Condvar` are [loom] 
+//! equivalents - for model checking. Outside of loom model, such build 
+//! does not work.
+//!
+//! [loom]: https://docs.rs/loom
+
+#[cfg(not(feature = "loom"))]
+pub(crate) use core::sync::atomic::{fence, AtomicBool, AtomicPtr, AtomicU64, AtomicUsize, Ordering};
+
+#[cfg(feature = "loom")]
+pub(crate) use loom::sync::atomic::{fence, AtomicBool, AtomicPtr, AtomicU64, AtomicUsize, Ordering};
+
+#[cfg(all(feature = "std", not(feature = "loom")))]
+use std::sync::Mutex as MutexImpl;
+#[cfg(all(feature = "std", not(feature = "loom")))]
+pub(crate) use std::sync::{Condvar, MutexGuard};
+
+#[cfg(feature = "loom")]
+use loom::sync::Mutex as MutexImpl;
+#[cfg(feature = "loom")]
+pub(crate) use loom::sync::{Condvar, MutexGuard};
+
+#[cfg(not(feature = "std"))]
+use spin::Mutex as MutexImpl;
+#[cfg(not(feature = "std"))]
+pub(crate) use spin::MutexGuard;
+
+/// Mutex, that ignores poisoning - guarded data is kept consistent 
+/// across panics.
+/// 
+/// `std` one, or spin-lock without `std` feature. [loom] one with `loom` feature.
+#[derive(Default)]
+pub(crate) struct Mutex<T>(MutexImpl<T>);
+
+impl<T> Mutex<T> {
+    #[inline]
+    pub fn new(value: T) -> Self {
+        Self(MutexImpl::new(value))
+    }
+    
+    #[inline]
+    pub fn lock(&self) -> MutexGuard<'_, T> {
+        #[cfg(feature = "std")]
+        return self.0.lock().unwrap_or_else(|e| e.into_inner());
+        #[cfg(not(feature = "std"))]
+        return self.0.lock();
+    }
+    
+    #[inline]
+    pub fn try_lock(&self) -> Option<MutexGuard<'_, T>> {
+        #[cfg(feature = "std")]
+        return match self.0.try_lock() {
+            Ok(guard) => Some(guard),
+            Err(std::sync::TryLockError::Poisoned(e)) => Some(e.into_inner()),
+            Err(std::sync::TryLockError::WouldBlock) => None,
+        };
+        #[cfg(not(feature = "std"))]
+        return self.0.try_lock();
+    }
+}
@@ -0,0 +1,167 @@
+use alloc::vec::Vec;
+use core::mem;
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::time::Instant;
+use crate::LendingReader;
+
+/// Monotonic time source for [TimeBatchReader].
+pub trait Clock {
+    /// Time elapsed since clock-specific epoch. Must never decrease.
+    fn now(&self) -> Duration;
+}
+
+/// [Clock] based on [Instant].
+#[cfg(feature = "std")]
+pub struct StdClock {
+    epoch: Instant
+}
+
+#[cfg(feature = "std")]
+impl Default for StdClock {
+    #[inline]
+    fn default() -> Self {
+        Self{ epoch: Instant::now() }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Clock for StdClock {
+    #[inline]
+    fn now(&self) -> Duration {
+        self.epoch.elapsed()
+    }
+}
+
+/// What [TimeBatchReader] does with a window without messages.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum EmptyBatchPolicy {
+    /// Yield empty batch.
+    #[default]
+    Yield,
+    /// Do not yield anything, just start the next window.
+    Skip,
+}
+
+/// Reader that batches messages by time window.
+///
+/// Accumulates cloned messages, until the clock advances past the window,
+/// then yields them as a whole.
+///
+/// ```
+/// # #[cfg(feature = "std")] {
+/// # use std::time::Duration;
+/// # use chute::{LendingReader, StdClock, TimeBatchReader};
+/// let mut queue = chute::spmc::Queue::new();
+/// let reader = queue.reader();
+/// let mut reader = TimeBatchReader::new(reader, StdClock::default(), Duration::from_millis(5));
+/// queue.push(1);
+/// queue.push(2);
+/// let batch = loop {
+///     if let Some(batch) = reader.next_batch() {
+///         break batch;
+///     }
+/// };
+/// assert_eq!(batch, [1, 2]);
+/// # }
+/// ```
+pub struct TimeBatchReader<R: LendingReader, C> {
+    reader: R,
+    clock : C,
+    window: Duration,
+    window_start: Duration,
+    policy: EmptyBatchPolicy,
+    batch : Vec<R::Item>,
+}
+
+impl<R, C> TimeBatchReader<R, C>
+where
+    R: LendingReader<Item: Clone>,
+    C: Clock
+{
+    /// First window starts now.
+    #[inline]
+    pub fn new(reader: R, clock: C, window: Duration) -> Self {
+        let window_start = clock.now();
+        Self{
+            reader,
+            clock,
+            window,
+            window_start,
+            policy: Default::default(),
+            batch: Vec::new(),
+        }
+    }
+
+    #[must_use]
+    #[inline]
+    pub fn with_policy(mut self, policy: EmptyBatchPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Returns accumulated batch, if the current window has passed.
+    ///
+    /// Returns `None` while window is in progress.
+    pub fn next_batch(&mut self) -> Option<Vec<R::Item>> {
+        while let Some(value) = self.reader.next() {
+            self.batch.push(value.clone());
+        }
+
+        let now = self.clock.now();
+        if now < self.window_start + self.window {
+            return None;
+        }
+        self.window_start = now;
+
+        if self.batch.is_empty() && self.policy == EmptyBatchPolicy::Skip {
+            return None;
+        }
+        Some(mem::take(&mut self.batch))
+    }
+
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+#[cfg(test)]
+mod test{
+    use std::cell::Cell;
+    use std::rc::Rc;
+    use std::time::Duration;
+    use crate::{spmc, Clock, EmptyBatchPolicy, TimeBatchReader};
+
+    struct ManualClock(Rc<Cell<Duration>>);
+    impl Clock for ManualClock {
+        fn now(&self) -> Duration {
+            self.0.get()
+        }
+    }
+
+    #[test]
+    fn time_batch_test() {
+        const WINDOW: Duration = Duration::from_millis(5);
+        let time = Rc::new(Cell::new(Duration::ZERO));
+
+        let mut queue: spmc::Queue<usize> = spmc::Queue::new();
+        let mut reader = TimeBatchReader::new(queue.reader(), ManualClock(time.clone()), WINDOW);
+        let mut skip_reader = TimeBatchReader::new(queue.reader(), ManualClock(time.clone()), WINDOW)
+            .with_policy(EmptyBatchPolicy::Skip);
+
+        queue.push(1);
+        queue.push(2);
+        assert_eq!(reader.next_batch(), None);
+        time.set(Duration::from_millis(4));
+        queue.push(3);
+        assert_eq!(reader.next_batch(), None);
+        time.set(Duration::from_millis(5));
+        assert_eq!(reader.next_batch(), Some(vec![1, 2, 3]));
+        assert_eq!(skip_reader.next_batch(), Some(vec![1, 2, 3]));
+
+        time.set(Duration::from_millis(10));
+        assert_eq!(reader.next_batch(), Some(vec![]));
+        assert_eq!(skip_reader.next_batch(), None);
+    }
+}
@@ -0,0 +1,109 @@
+//! Reader construction must not allocate, once the queue has seen
+//! as many live readers before. Reading must not allocate at all.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::sync::Arc;
+
+struct CountingAlloc;
+
+thread_local! {
+    /// Allocations made by the current thread.
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+fn allocations<R>(f: impl FnOnce() -> R) -> (usize, R) {
+    let before = ALLOCATIONS.with(Cell::get);
+    let result = f();
+    (ALLOCATIONS.with(Cell::get) - before, result)
+}
+
+#[test]
+fn spmc_reader() {
+    let mut queue: chute::spmc::Queue<usize> = Default::default();
+    queue.push(0);
+    {
+        // Warm up.
+        let reader = queue.reader();
+        let _clone = reader.clone();
+    }
+
+    let (count, reader) = allocations(|| queue.reader());
+    assert_eq!(count, 0);
+    let (count, _clone) = allocations(|| reader.clone());
+    assert_eq!(count, 0);
+}
+
+#[test]
+fn mpmc_reader() {
+    let queue: Arc<chute::mpmc::Queue<usize>> = Default::default();
+    queue.blocking_push(0);
+    {
+        // Warm up.
+        let reader = queue.reader();
+        let _clone = reader.clone();
+    }
+
+    let (count, reader) = allocations(|| queue.reader());
+    assert_eq!(count, 0);
+    let (count, _clone) = allocations(|| reader.clone());
+    assert_eq!(count, 0);
+}
+
+#[test]
+fn spmc_next() {
+    use chute::LendingReader;
+    
+    let mut queue: chute::spmc::Queue<usize> = Default::default();
+    let mut reader = queue.reader();
+    for i in 0..10_000 {
+        queue.push(i);
+    }
+
+    // Steady state - across block boundaries too.
+    let (count, sum) = allocations(|| {
+        let mut sum = 0;
+        while let Some(value) = reader.next() {
+            sum += value;
+        }
+        sum
+    });
+    assert_eq!(count, 0);
+    assert_eq!(sum, (0..10_000).sum());
+}
+
+#[test]
+fn mpmc_next() {
+    use chute::LendingReader;
+    
+    let queue: Arc<chute::mpmc::Queue<usize>> = Default::default();
+    let mut reader = queue.reader();
+    let mut writer = queue.writer();
+    for i in 0..10_000 {
+        writer.push(i);
+    }
+
+    let (count, sum) = allocations(|| {
+        let mut sum = 0;
+        while let Some(value) = reader.next() {
+            sum += value;
+        }
+        sum
+    });
+    assert_eq!(count, 0);
+    assert_eq!(sum, (0..10_000).sum());
+}
@@ -0,0 +1,236 @@
+//! Every message must be dropped exactly once - no matter who releases
+//! the block last: queue, reader, or read guard.
+
+use chute::LendingReader;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+const N: usize = 64;
+
+/// Drop count of each message.
+struct Drops(Vec<AtomicUsize>);
+
+impl Drops {
+    fn new(len: usize) -> Arc<Self> {
+        Arc::new(Self((0..len).map(|_| AtomicUsize::new(0)).collect()))
+    }
+
+    fn total(&self) -> usize {
+        self.0.iter().map(|count| count.load(Ordering::Relaxed)).sum()
+    }
+
+    #[track_caller]
+    fn assert_none(&self) {
+        assert_eq!(self.total(), 0);
+    }
+
+    #[track_caller]
+    fn assert_all_once(&self) {
+        for (index, count) in self.0.iter().enumerate() {
+            assert_eq!(count.load(Ordering::Relaxed), 1, "message {index}");
+        }
+    }
+}
+
+struct DropCounter {
+    index: usize,
+    drops: Arc<Drops>,
+}
+
+impl DropCounter {
+    fn new(drops: &Arc<Drops>, index: usize) -> Self {
+        Self{ index, drops: drops.clone() }
+    }
+
+    /// Checks, that message was not dropped yet.
+    #[track_caller]
+    fn check(&self, index: usize) {
+        assert_eq!(self.index, index);
+        assert_eq!(self.drops.0[index].load(Ordering::Relaxed), 0);
+    }
+}
+
+impl Drop for DropCounter {
+    fn drop(&mut self) {
+        self.drops.0[self.index].fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[test]
+fn spmc_partially_read() {
+    const COUNT: usize = N*3 + 10;
+    let drops = Drops::new(COUNT);
+    let mut queue: chute::spmc::Queue<DropCounter, N> = Default::default();
+    let mut reader0 = queue.reader();
+    let mut reader1 = queue.reader();
+    let mut reader2 = queue.reader();
+    for i in 0..COUNT {
+        queue.push(DropCounter::new(&drops, i));
+    }
+    reader0.next().unwrap().check(0);
+    for i in 0..N+10 {
+        reader1.next().unwrap().check(i);
+    }
+    let reader3 = reader1.clone();
+    while reader2.next().is_some() {}
+
+    drop(queue);
+    drops.assert_none();
+
+    // Readers still can read, after queue drop.
+    for i in N+10..N+20 {
+        reader1.next().unwrap().check(i);
+    }
+
+    // reader3 now holds the oldest reachable block.
+    drop(reader0);
+    assert_eq!(drops.total(), N);
+    drop(reader3);
+    assert_eq!(drops.total(), N);
+
+    drop(reader2);
+    drop(reader1);
+    drops.assert_all_once();
+}
+
+#[test]
+fn spmc_max_blocks() {
+    const COUNT: usize = N*8 + 10;
+    let drops = Drops::new(COUNT);
+    let mut queue = chute::spmc::Queue::<DropCounter, N>::default().with_max_blocks(2);
+    let mut reader = queue.reader();
+    for i in 0..COUNT {
+        queue.push(DropCounter::new(&drops, i));
+    }
+    // Reader keeps its block, and the blocks after it.
+    drops.assert_none();
+
+    // Skipped messages are dropped with their blocks, as reader leaves them.
+    let mut read = 0;
+    while let Some(value) = reader.next() {
+        assert_eq!(value.drops.0[value.index].load(Ordering::Relaxed), 0);
+        read += 1;
+    }
+    assert_eq!(read + reader.skipped(), COUNT);
+    assert_eq!(drops.total(), N*8);
+
+    drop(queue);
+    drop(reader);
+    drops.assert_all_once();
+}
+
+#[test]
+fn spmc_writer_and_guards() {
+    const COUNT: usize = N*3 + 10;
+    let drops = Drops::new(COUNT);
+    let mut queue: chute::spmc::Queue<DropCounter, N> = Default::default();
+    let mut reader = queue.reader();
+    {
+        let mut writer = queue.writer();
+        for i in 0..COUNT {
+            writer.push(DropCounter::new(&drops, i));
+        }
+    }
+    let guard0 = reader.next_guard().unwrap();
+    for _ in 0..N*2 {
+        reader.next().unwrap();
+    }
+    let guard1 = reader.next_guard().unwrap();
+
+    // Guards outlive both queue and reader.
+    drop(queue);
+    drop(reader);
+    guard0.check(0);
+    guard1.check(N*2 + 1);
+
+    drop(guard0);
+    assert_eq!(drops.total(), N*2);
+    drop(guard1);
+    drops.assert_all_once();
+}
+
+#[test]
+fn mpmc_partially_read() {
+    const WRITERS: usize = 4;
+    const PER_WRITER: usize = N*4 + 10;
+    const COUNT: usize = WRITERS * PER_WRITER;
+    let drops = Drops::new(COUNT);
+    let queue: Arc<chute::mpmc::Queue<DropCounter, N>> = Default::default();
+    let readers: Vec<_> = (0..3).map(|_| queue.reader()).collect();
+
+    let writers: Vec<_> = (0..WRITERS).map(|w| {
+        let mut writer = queue.writer();
+        let drops = drops.clone();
+        std::thread::spawn(move || {
+            for i in 0..PER_WRITER {
+                let value = DropCounter::new(&drops, w*PER_WRITER + i);
+                if i % 3 == 0 {
+                    writer.push_group(&mut vec![value]);
+                } else {
+                    writer.push(value);
+                }
+                // Aborted slots are never written - nothing to drop.
+                if i % 7 == 0 {
+                    drop(writer.begin_push());
+                }
+            }
+        })
+    }).collect();
+
+    // Read different amounts, concurrently with writers.
+    let readers: Vec<_> = readers.into_iter().enumerate().map(|(r, mut reader)| {
+        std::thread::spawn(move || {
+            let mut read = 0;
+            while read < r * COUNT / 3 {
+                if let Some(value) = reader.next() {
+                    assert_eq!(value.drops.0[value.index].load(Ordering::Relaxed), 0);
+                    read += 1;
+                }
+            }
+            reader
+        })
+    }).collect();
+    for writer in writers {
+        writer.join().unwrap();
+    }
+    let mut readers: Vec<_> = readers.into_iter()
+        .map(|reader| reader.join().unwrap())
+        .collect();
+
+    drop(queue);
+    drops.assert_none();
+
+    // Last reader frees the rest.
+    let mut last = readers.remove(0);
+    drop(readers);
+    let mut read = 0;
+    while last.next().is_some() {
+        read += 1;
+    }
+    assert_eq!(read, COUNT);
+    drop(last);
+    drops.assert_all_once();
+}
+
+#[test]
+fn mpmc_reader_dropped_first() {
+    const COUNT: usize = N*2 + 10;
+    let drops = Drops::new(COUNT);
+    let queue: Arc<chute::mpmc::Queue<DropCounter, N>> = Default::default();
+    let mut reader = queue.reader();
+    let mut writer = queue.writer();
+    for i in 0..COUNT {
+        writer.push(DropCounter::new(&drops, i));
+    }
+    reader.next().unwrap().check(0);
+    let guard = reader.next_guard().unwrap();
+
+    drop(reader);
+    drop(writer);
+    drop(queue);
+    // Guard keeps its block, and the blocks after it.
+    drops.assert_none();
+    guard.check(1);
+    drop(guard);
+    drops.assert_all_once();
+}
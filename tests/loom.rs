@@ -0,0 +1,113 @@
+//! Model checking of the lock-free core with [loom].
+//! 
+//! Run with `cargo test --release --features loom --test loom`.
+//! Queues use the smallest block size - to reach block switch quickly.
+//! 
+//! [loom]: https://docs.rs/loom
+#![cfg(feature = "loom")]
+
+use std::sync::Arc;
+use loom::thread;
+use chute::LendingReader;
+
+/// Block size of tested queues.
+const N: usize = 64;
+
+/// Writer switches to the next block, while reader reads.
+#[test]
+fn mpmc_reader_block_handoff() {
+    loom::model(|| {
+        let queue: Arc<chute::mpmc::Queue<usize, N>> = Default::default();
+        let mut reader = queue.reader();
+        let mut writer = queue.writer();
+        for i in 0..N-1 {
+            writer.push(i);
+        }
+        for i in 0..N-2 {
+            assert_eq!(reader.next(), Some(&i));
+        }
+        
+        let join = thread::spawn(move || {
+            writer.push(N-1);
+            writer.push(N);
+        });
+        
+        let mut expected = N-2;
+        for _ in 0..3 {
+            if let Some(&value) = reader.next() {
+                assert_eq!(value, expected);
+                expected += 1;
+            }
+        }
+        join.join().unwrap();
+        
+        while let Some(&value) = reader.next() {
+            assert_eq!(value, expected);
+            expected += 1;
+        }
+        assert_eq!(expected, N+1);
+    });
+}
+
+/// Two writers race for the last slot, and for the new block insert.
+#[test]
+fn mpmc_writers_block_handoff() {
+    loom::model(|| {
+        let queue: Arc<chute::mpmc::Queue<usize, N>> = Default::default();
+        let mut reader = queue.reader();
+        let mut writer = queue.writer();
+        for i in 0..N-1 {
+            writer.push(i);
+        }
+        
+        let joins: Vec<_> = [N-1, N].into_iter().map(|value| {
+            let mut writer = queue.writer();
+            thread::spawn(move || writer.push(value))
+        }).collect();
+        for join in joins {
+            join.join().unwrap();
+        }
+        
+        let mut values = Vec::new();
+        while let Some(&value) = reader.next() {
+            values.push(value);
+        }
+        values[N-1..].sort();
+        assert!(values.into_iter().eq(0..N+1));
+    });
+}
+
+/// Writer switches to the next block, while reader reads.
+#[test]
+fn spmc_reader_block_handoff() {
+    loom::model(|| {
+        let mut queue: chute::spmc::Queue<usize, N> = Default::default();
+        let mut reader = queue.reader();
+        for i in 0..N-1 {
+            queue.push(i);
+        }
+        for i in 0..N-2 {
+            assert_eq!(reader.next(), Some(&i));
+        }
+        
+        let join = thread::spawn(move || {
+            queue.push(N-1);
+            queue.push(N);
+        });
+        
+        let mut expected = N-2;
+        for _ in 0..3 {
+            if let Some(&value) = reader.next() {
+                assert_eq!(value, expected);
+                expected += 1;
+            }
+        }
+        join.join().unwrap();
+        
+        while let Some(&value) = reader.next() {
+            assert_eq!(value, expected);
+            expected += 1;
+        }
+        assert_eq!(expected, N+1);
+    });
+}
@@ -0,0 +1,87 @@
+//! With zeroize on, freed blocks must not contain messages.
+
+use chute::LendingReader;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+use std::sync::Arc;
+
+const PATTERN: u8 = 0xA5;
+type Secret = [u8; 32];
+const SECRET: Secret = [PATTERN; 32];
+/// Spans several blocks.
+const COUNT: usize = 4096*3;
+
+struct ScanningAlloc;
+
+thread_local! {
+    /// Deallocations by the current thread, that still contain `SECRET`.
+    static DIRTY: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for ScanningAlloc {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        let bytes = std::slice::from_raw_parts(ptr, layout.size());
+        if bytes.windows(SECRET.len()).any(|w| w == SECRET) {
+            DIRTY.with(|count| count.set(count.get() + 1));
+        }
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: ScanningAlloc = ScanningAlloc;
+
+fn dirty(f: impl FnOnce()) -> usize {
+    let before = DIRTY.with(Cell::get);
+    f();
+    DIRTY.with(Cell::get) - before
+}
+
+#[test]
+fn spsc() {
+    for zeroize in [true, false] {
+        let count = dirty(|| {
+            let (producer, mut consumer) = chute::spsc::queue::<Secret>();
+            let mut producer = producer.with_zeroize(zeroize);
+            for _ in 0..COUNT {
+                producer.push(SECRET);
+            }
+            while consumer.next().is_some() {}
+        });
+        assert_eq!(count == 0, zeroize);
+    }
+}
+
+#[test]
+fn spmc() {
+    for zeroize in [true, false] {
+        let count = dirty(|| {
+            let mut queue = chute::spmc::Queue::<Secret>::new().with_zeroize(zeroize);
+            let _reader = queue.reader();
+            for _ in 0..COUNT {
+                queue.push(SECRET);
+            }
+        });
+        assert_eq!(count == 0, zeroize);
+    }
+}
+
+#[test]
+fn mpmc() {
+    for zeroize in [true, false] {
+        let count = dirty(|| {
+            let queue: Arc<chute::mpmc::Queue<Secret>> =
+                Arc::new(chute::mpmc::Queue::default().with_zeroize(zeroize));
+            let _reader = queue.reader();
+            let mut writer = queue.writer();
+            for _ in 0..COUNT {
+                writer.push(SECRET);
+            }
+        });
+        assert_eq!(count == 0, zeroize);
+    }
+}